@@ -0,0 +1 @@
+ALTER TABLE todo ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- 之前存的是本地时间, 按当前系统时区转换为 UTC
+UPDATE todo SET expire_time = datetime(expire_time, 'utc') WHERE expire_time IS NOT NULL;
+ALTER TABLE todo ADD COLUMN timezone TEXT DEFAULT NULL;
@@ -0,0 +1,2 @@
+-- 全天的 todo 只存日期 (当天 00:00), 不做时区转换
+ALTER TABLE todo ADD COLUMN all_day BOOLEAN NOT NULL DEFAULT 0;
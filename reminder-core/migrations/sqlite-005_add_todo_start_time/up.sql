@@ -0,0 +1,3 @@
+-- start_time 和 expire_time 的存储方式相同, expire_time 同时作为结束时间
+ALTER TABLE todo ADD COLUMN start_time DATETIME DEFAULT NULL;
+ALTER TABLE todo ADD COLUMN hide_until_start BOOLEAN NOT NULL DEFAULT 0;
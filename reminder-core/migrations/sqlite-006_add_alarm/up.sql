@@ -0,0 +1,7 @@
+-- 每个 todo 可以有多个提醒, trigger_time 为 UTC 的绝对时间, 为 NULL 时使用 trigger_offset (到期前的分钟数)
+CREATE TABLE IF NOT EXISTS alarm (
+    id             INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    todo_id        INTEGER NOT NULL REFERENCES todo(id) ON DELETE CASCADE,
+    trigger_time   DATETIME DEFAULT NULL,
+    trigger_offset INTEGER  DEFAULT NULL
+);
@@ -0,0 +1,4 @@
+-- content 只保留第一行作为标题, 其余部分移到 notes
+ALTER TABLE todo ADD COLUMN notes TEXT NOT NULL DEFAULT '';
+UPDATE todo SET content = substr(content, 1, instr(content, char(10)) - 1), notes = substr(content, instr(content, char(10)) + 1)
+    WHERE instr(content, char(10)) > 0;
@@ -0,0 +1,6 @@
+-- 附件只保存 URI, 本地文件为 file:// 开头, 不复制文件内容
+CREATE TABLE IF NOT EXISTS attachment (
+    id      INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    todo_id INTEGER NOT NULL REFERENCES todo(id) ON DELETE CASCADE,
+    uri     TEXT    NOT NULL
+);
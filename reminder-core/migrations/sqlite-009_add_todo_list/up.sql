@@ -0,0 +1,7 @@
+-- todo 可以放到某个清单中, list_id 为 NULL 时不属于任何清单
+CREATE TABLE IF NOT EXISTS todo_list (
+    id    INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name  TEXT    NOT NULL,
+    color TEXT    NOT NULL DEFAULT '#3584e4'
+);
+ALTER TABLE todo ADD COLUMN list_id INTEGER DEFAULT NULL REFERENCES todo_list(id);
@@ -0,0 +1,2 @@
+-- 已完成的 todo 仍然保留, 不再触发提醒
+ALTER TABLE todo ADD COLUMN completed BOOLEAN NOT NULL DEFAULT 0;
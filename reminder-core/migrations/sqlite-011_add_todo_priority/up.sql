@@ -0,0 +1,2 @@
+-- 优先级: 0 无, 1 低, 2 中, 3 高
+ALTER TABLE todo ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
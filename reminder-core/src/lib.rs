@@ -0,0 +1,19 @@
+#[macro_use]
+extern crate diesel;
+#[macro_use]
+extern crate diesel_migrations;
+
+// 数据层, 不依赖 GTK, 供 GUI 和脚本共用
+
+mod models;
+mod schema;
+mod repository;
+mod store;
+mod memory;
+mod timezone;
+
+pub use memory::MemoryTodoStore;
+pub use models::{Alarm, Attachment, NewAlarm, NewAttachment, NewTodo, NewTodoList, OpenError, Todo, TodoList, PRIORITY_HIGH, PRIORITY_LOW, PRIORITY_MEDIUM, PRIORITY_NONE};
+pub use repository::{StoreError, StoreResult, TodoRepository};
+pub use store::TodoStore;
+pub use timezone::{local_date, local_day_start, parse_timezone, resolve_local, timezone_names, utc_to_wall, wall_to_utc};
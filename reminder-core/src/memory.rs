@@ -0,0 +1,232 @@
+use std::cell::RefCell;
+
+use chrono::{Date, Local, Utc};
+
+use crate::models::{Alarm, Attachment, NewAlarm, NewAttachment, NewTodo, NewTodoList, Todo, todo_days_in_month, TodoList};
+use crate::repository::{StoreResult, TodoRepository};
+
+// 只存在内存里的存储, 用于测试, 排序规则和 sqlite 实现保持一致
+pub struct MemoryTodoStore {
+    todos: RefCell<Vec<Todo>>,
+    next_id: RefCell<i32>,
+    lists: RefCell<Vec<TodoList>>,
+    next_list_id: RefCell<i32>,
+    alarms: RefCell<Vec<Alarm>>,
+    next_alarm_id: RefCell<i32>,
+    attachments: RefCell<Vec<Attachment>>,
+    next_attachment_id: RefCell<i32>,
+}
+
+impl Default for MemoryTodoStore {
+    fn default() -> Self {
+        MemoryTodoStore::new()
+    }
+}
+
+impl MemoryTodoStore {
+    pub fn new() -> MemoryTodoStore {
+        MemoryTodoStore {
+            todos: RefCell::new(Vec::new()),
+            next_id: RefCell::new(1),
+            lists: RefCell::new(Vec::new()),
+            next_list_id: RefCell::new(1),
+            alarms: RefCell::new(Vec::new()),
+            next_alarm_id: RefCell::new(1),
+            attachments: RefCell::new(Vec::new()),
+            next_attachment_id: RefCell::new(1),
+        }
+    }
+
+    // 和 sqlite 实现一样, todo 已经被删除时不写入提醒和附件
+    fn todo_exists(&self, todo_id: i32) -> bool {
+        self.todos.borrow().iter().any(|todo| todo.id == todo_id)
+    }
+}
+
+impl TodoRepository for MemoryTodoStore {
+    fn new_todo(&self, todo: &NewTodo) -> StoreResult<i32> {
+        let position = match todo.expire_time {
+            Some(_) => 0,
+            None => self.next_undated_position()?,
+        };
+
+        let mut next_id = self.next_id.borrow_mut();
+        self.todos.borrow_mut().push(Todo {
+            id: *next_id,
+            content: todo.content.clone(),
+            expire_time: todo.expire_time,
+            position,
+            timezone: todo.timezone.clone(),
+            all_day: todo.all_day,
+            start_time: todo.start_time,
+            hide_until_start: todo.hide_until_start,
+            notes: todo.notes.clone(),
+            list_id: todo.list_id,
+            completed: false,
+            priority: todo.priority,
+        });
+        *next_id += 1;
+        Ok(*next_id - 1)
+    }
+
+    fn next_undated_position(&self) -> StoreResult<i32> {
+        Ok(self.todos.borrow().iter().filter(|todo| todo.expire_time.is_none()).map(|todo| todo.position).max().unwrap_or(0) + 1)
+    }
+
+    fn find_todo(&self, date: Option<Date<Local>>, list_id: Option<i32>) -> StoreResult<Vec<Todo>> {
+        let now = Utc::now().naive_utc();
+        let in_list = |todo: &&Todo| list_id.is_none() || todo.list_id == list_id;
+        let mut todos: Vec<Todo> = match date {
+            Some(date) => self.todos.borrow().iter().filter(in_list).filter(|todo| todo.is_on_date(date.naive_local())).cloned().collect(),
+            None => self.todos.borrow().iter().filter(in_list).filter(|todo| todo.expire_time.is_none() && !todo.is_hidden(&now)).cloned().collect(),
+        };
+        match date {
+            Some(_) => todos.sort_by_key(|todo| (todo.position, !todo.all_day, todo.expire_time)),
+            None => todos.sort_by_key(|todo| (todo.position, todo.id)),
+        }
+        Ok(todos)
+    }
+
+    fn get_todo(&self, todo_id: i32) -> StoreResult<Option<Todo>> {
+        Ok(self.todos.borrow().iter().find(|todo| todo.id == todo_id).cloned())
+    }
+
+    fn all_todo(&self) -> StoreResult<Vec<Todo>> {
+        Ok(self.todos.borrow().clone())
+    }
+
+    fn del_todo(&self, todo_id: &[i32]) -> StoreResult<()> {
+        self.todos.borrow_mut().retain(|todo| !todo_id.contains(&todo.id));
+        self.alarms.borrow_mut().retain(|alarm| !todo_id.contains(&alarm.todo_id));
+        self.attachments.borrow_mut().retain(|attachment| !todo_id.contains(&attachment.todo_id));
+        Ok(())
+    }
+
+    fn update_todo(&self, todo: &Todo) -> StoreResult<()> {
+        for t in self.todos.borrow_mut().iter_mut() {
+            if t.id == todo.id {
+                *t = todo.clone();
+            }
+        }
+        Ok(())
+    }
+
+    fn set_completed(&self, todo_id: i32, completed: bool) -> StoreResult<()> {
+        for t in self.todos.borrow_mut().iter_mut() {
+            if t.id == todo_id {
+                t.completed = completed;
+            }
+        }
+        Ok(())
+    }
+
+    fn reorder_todo(&self, todo_id: &[i32]) -> StoreResult<()> {
+        for t in self.todos.borrow_mut().iter_mut() {
+            if let Some(position) = todo_id.iter().position(|id| *id == t.id) {
+                t.position = position as i32 + 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn get_exists_day(&self, year: i32, month: i32, list_id: Option<i32>) -> StoreResult<Vec<i32>> {
+        let todos: Vec<Todo> = self.todos.borrow().iter().filter(|todo| list_id.is_none() || todo.list_id == list_id).cloned().collect();
+        Ok(todo_days_in_month(&todos, year, month))
+    }
+
+    fn all_list(&self) -> StoreResult<Vec<TodoList>> {
+        Ok(self.lists.borrow().clone())
+    }
+
+    fn new_list(&self, list: &NewTodoList) -> StoreResult<i32> {
+        let mut next_list_id = self.next_list_id.borrow_mut();
+        self.lists.borrow_mut().push(TodoList {
+            id: *next_list_id,
+            name: list.name.clone(),
+            color: list.color.clone(),
+        });
+        *next_list_id += 1;
+        Ok(*next_list_id - 1)
+    }
+
+    fn update_list(&self, list: &TodoList) -> StoreResult<()> {
+        for l in self.lists.borrow_mut().iter_mut() {
+            if l.id == list.id {
+                *l = list.clone();
+            }
+        }
+        Ok(())
+    }
+
+    fn del_list(&self, list_id: i32) -> StoreResult<()> {
+        for t in self.todos.borrow_mut().iter_mut() {
+            if t.list_id == Some(list_id) {
+                t.list_id = None;
+            }
+        }
+        self.lists.borrow_mut().retain(|l| l.id != list_id);
+        Ok(())
+    }
+
+    fn find_alarm(&self, todo_id: i32) -> StoreResult<Vec<Alarm>> {
+        Ok(self.alarms.borrow().iter().filter(|alarm| alarm.todo_id == todo_id).cloned().collect())
+    }
+
+    fn all_alarm(&self) -> StoreResult<Vec<Alarm>> {
+        Ok(self.alarms.borrow().clone())
+    }
+
+    fn set_alarm(&self, todo_id: i32, alarms: &[NewAlarm]) -> StoreResult<()> {
+        if !self.todo_exists(todo_id) {
+            return Ok(());
+        }
+        self.alarms.borrow_mut().retain(|alarm| alarm.todo_id != todo_id);
+
+        let mut next_alarm_id = self.next_alarm_id.borrow_mut();
+        for a in alarms.iter() {
+            self.alarms.borrow_mut().push(Alarm {
+                id: *next_alarm_id,
+                todo_id,
+                trigger_time: a.trigger_time,
+                trigger_offset: a.trigger_offset,
+            });
+            *next_alarm_id += 1;
+        }
+        Ok(())
+    }
+
+    fn find_attachment(&self, todo_id: i32) -> StoreResult<Vec<Attachment>> {
+        Ok(self.attachments.borrow().iter().filter(|attachment| attachment.todo_id == todo_id).cloned().collect())
+    }
+
+    fn all_attachment(&self) -> StoreResult<Vec<Attachment>> {
+        Ok(self.attachments.borrow().clone())
+    }
+
+    fn add_attachment(&self, todo_id: i32, attachment: &NewAttachment) -> StoreResult<()> {
+        if !self.todo_exists(todo_id) {
+            return Ok(());
+        }
+        let mut next_attachment_id = self.next_attachment_id.borrow_mut();
+        self.attachments.borrow_mut().push(Attachment {
+            id: *next_attachment_id,
+            todo_id,
+            uri: attachment.uri.clone(),
+        });
+        *next_attachment_id += 1;
+        Ok(())
+    }
+
+    fn set_attachment(&self, todo_id: i32, attachments: &[NewAttachment]) -> StoreResult<()> {
+        self.attachments.borrow_mut().retain(|attachment| attachment.todo_id != todo_id);
+        for a in attachments.iter() {
+            self.add_attachment(todo_id, a)?;
+        }
+        Ok(())
+    }
+
+    // 只有自己能修改, 不会被外部改变
+    fn data_version(&self) -> StoreResult<i64> {
+        Ok(0)
+    }
+}
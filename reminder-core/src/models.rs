@@ -0,0 +1,569 @@
+use std::fmt;
+use std::path;
+use std::fs::create_dir_all;
+
+use chrono::{Date, DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+
+use super::schema::{alarm, attachment, todo, todo_list};
+use super::timezone::{resolve_local, utc_to_wall, wall_to_utc};
+
+pub const PRIORITY_NONE: i32 = 0;
+pub const PRIORITY_LOW: i32 = 1;
+pub const PRIORITY_MEDIUM: i32 = 2;
+pub const PRIORITY_HIGH: i32 = 3;
+
+#[derive(Queryable, Clone)]
+pub struct Todo {
+    pub id: i32,
+    pub content: String, // 标题, 只有一行
+    pub expire_time: Option<NaiveDateTime>, // UTC, 全天的 todo 为当天 00:00, 不做时区转换
+    pub position: i32,
+    pub timezone: Option<String>, // 为 None 时跟随本地时区
+    pub all_day: bool,
+    pub start_time: Option<NaiveDateTime>, // 存储方式和 expire_time 相同
+    pub hide_until_start: bool,
+    pub notes: String,
+    pub list_id: Option<i32>, // 为 None 时不属于任何清单
+    pub completed: bool,
+    pub priority: i32, // PRIORITY_NONE 到 PRIORITY_HIGH
+}
+
+impl Todo {
+    fn to_local(&self, time: &NaiveDateTime) -> DateTime<Local> {
+        match self.all_day {
+            true => resolve_local(&Local, time),
+            false => Local.from_utc_datetime(time),
+        }
+    }
+
+    fn to_wall(&self, time: &NaiveDateTime) -> NaiveDateTime {
+        match self.all_day {
+            true => *time,
+            false => utc_to_wall(time, self.timezone.as_deref()),
+        }
+    }
+
+    pub fn local_expire_time(&self) -> Option<DateTime<Local>> {
+        self.expire_time.map(|t| self.to_local(&t))
+    }
+
+    // todo 自己时区中的时间, 例如东京的 9:00
+    pub fn wall_expire_time(&self) -> Option<NaiveDateTime> {
+        self.expire_time.map(|t| self.to_wall(&t))
+    }
+
+    // todo 显示在日历的哪一天
+    pub fn local_date(&self) -> Option<NaiveDate> {
+        match self.all_day {
+            true => self.expire_time.map(|t| t.date()),
+            false => self.local_expire_time().map(|t| t.date().naive_local()),
+        }
+    }
+
+    pub fn local_start_time(&self) -> Option<DateTime<Local>> {
+        self.start_time.map(|t| self.to_local(&t))
+    }
+
+    pub fn wall_start_time(&self) -> Option<NaiveDateTime> {
+        self.start_time.map(|t| self.to_wall(&t))
+    }
+
+    pub fn local_start_date(&self) -> Option<NaiveDate> {
+        match self.all_day {
+            true => self.start_time.map(|t| t.date()),
+            false => self.local_start_time().map(|t| t.date().naive_local()),
+        }
+    }
+
+    // 有开始时间的 todo 从开始那天到到期那天每天都会显示
+    pub fn is_on_date(&self, date: NaiveDate) -> bool {
+        match self.local_date() {
+            Some(end) => self.local_start_date().unwrap_or(end).min(end) <= date && date <= end,
+            None => false,
+        }
+    }
+
+    // 设置了 hide_until_start 的 todo 在开始之前不出现在没有日期的列表中, now 为 UTC
+    pub fn is_hidden(&self, now: &NaiveDateTime) -> bool {
+        self.hide_until_start && self.start_time.is_some_and(|t| t > *now)
+    }
+
+    // 移到本地日历上的 date, date 为 None 时变成没有日期的 todo. 时间在 todo 自己的时区中平移, 跨过夏令时后墙上时间不变
+    pub fn moved_to_date(&self, date: Option<NaiveDate>) -> Todo {
+        let all_day = self.all_day || self.expire_time.is_none(); // 从没有日期拖到日历上的 todo 作为全天的
+        let days = match (self.local_date(), date) {
+            (Some(old_date), Some(date)) => date - old_date,
+            _ => Duration::zero(),
+        };
+        let to_utc = |wall: NaiveDateTime| wall_to_utc(&wall, self.timezone.as_deref());
+
+        let expire_time = date.map(|date| match self.wall_expire_time() {
+            Some(wall) if !all_day => to_utc(wall + days), // 保留原来的时间
+            _ => date.and_hms(0, 0, 0),
+        });
+
+        // 跨多天的 todo 整体平移, 开始时间的存储方式跟着 all_day 变化
+        let start_time = match (self.wall_start_time().map(|start| start + days), expire_time) {
+            (Some(start), Some(expire_time)) if all_day => Some(start.date().and_hms(0, 0, 0).min(expire_time)),
+            (Some(start), Some(expire_time)) => Some(to_utc(start).min(expire_time)),
+            (Some(start), None) => Some(to_utc(start)),
+            (None, _) => None,
+        };
+
+        Todo {
+            expire_time,
+            all_day: all_day && expire_time.is_some(),
+            start_time,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "todo"]
+pub struct NewTodo {
+    pub content: String,
+    pub expire_time: Option<NaiveDateTime>, // UTC, 全天的 todo 为当天 00:00
+    pub timezone: Option<String>,
+    pub all_day: bool,
+    pub start_time: Option<NaiveDateTime>,
+    pub hide_until_start: bool,
+    pub notes: String,
+    pub list_id: Option<i32>,
+    pub priority: i32,
+}
+
+#[derive(Queryable, Clone)]
+pub struct TodoList {
+    pub id: i32,
+    pub name: String,
+    pub color: String, // #rrggbb
+}
+
+#[derive(Insertable)]
+#[table_name = "todo_list"]
+pub struct NewTodoList {
+    pub name: String,
+    pub color: String,
+}
+
+#[derive(Queryable, Clone)]
+pub struct Alarm {
+    pub id: i32,
+    pub todo_id: i32,
+    pub trigger_time: Option<NaiveDateTime>, // UTC
+    pub trigger_offset: Option<i32>, // 到期前的分钟数, 负数为到期之后
+}
+
+impl Alarm {
+    // 提醒的 UTC 时间, 相对提醒在 todo 没有到期时间时不会触发
+    pub fn fire_time(&self, todo: &Todo) -> Option<NaiveDateTime> {
+        match (self.trigger_time, self.trigger_offset) {
+            (Some(time), _) => Some(time),
+            (None, Some(offset)) => todo.local_expire_time().map(|t| t.naive_utc() - Duration::minutes(offset as i64)),
+            (None, None) => None,
+        }
+    }
+
+    pub fn to_new_alarm(&self) -> NewAlarm {
+        NewAlarm {
+            trigger_time: self.trigger_time,
+            trigger_offset: self.trigger_offset,
+        }
+    }
+}
+
+// trigger_time 和 trigger_offset 只设置一个
+#[derive(Insertable, Clone, PartialEq, Debug)]
+#[table_name = "alarm"]
+pub struct NewAlarm {
+    pub trigger_time: Option<NaiveDateTime>,
+    pub trigger_offset: Option<i32>,
+}
+
+#[derive(Queryable, Clone)]
+pub struct Attachment {
+    pub id: i32,
+    pub todo_id: i32,
+    pub uri: String, // 本地文件为 file:// URI
+}
+
+impl Attachment {
+    pub fn to_new_attachment(&self) -> NewAttachment {
+        NewAttachment {
+            uri: self.uri.clone(),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, PartialEq, Debug)]
+#[table_name = "attachment"]
+pub struct NewAttachment {
+    pub uri: String,
+}
+
+// 返回 todos 在该月中占用的日期 (升序, 不重复)
+pub(crate) fn todo_days_in_month(todos: &[Todo], year: i32, month: i32) -> Vec<i32> {
+    let mut days = Vec::new();
+    for todo in todos.iter() {
+        let end = match todo.local_date() {
+            Some(end) => end,
+            None => continue,
+        };
+        let mut date = todo.local_start_date().unwrap_or(end).min(end);
+        while date <= end {
+            if date.year() == year && date.month() as i32 == month {
+                days.push(date.day() as i32);
+            }
+            date = date.succ();
+        }
+    }
+    days.sort();
+    days.dedup();
+    days
+}
+
+
+no_arg_sql_function!(last_insert_rowid, diesel::sql_types::Integer);
+
+const BUSY_TIMEOUT_MS: u32 = 5000;
+const BUSY_RETRY_COUNT: u64 = 5;
+
+fn is_busy_error(e: &diesel::result::Error) -> bool {
+    match e {
+        diesel::result::Error::DatabaseError(_, info) => info.message().contains("database is locked"),
+        _ => false,
+    }
+}
+
+// busy_timeout 之外, 读事务升级为写事务时如果快照已经过期 sqlite 会直接返回 busy, 需要把整个操作重试一遍
+fn retry_busy<T, F: Fn() -> QueryResult<T>>(f: F) -> QueryResult<T> {
+    let mut retry = 0;
+    loop {
+        match f() {
+            Err(e) if is_busy_error(&e) && retry < BUSY_RETRY_COUNT => {
+                retry += 1;
+                std::thread::sleep(std::time::Duration::from_millis(50 * retry));
+            }
+            result => return result,
+        }
+    }
+}
+
+// todo 可能已经被其他进程删除, 这时写入提醒或附件会违反外键约束, 所以在事务中先检查
+fn todo_exists(conn: &SqliteConnection, todo_id: i32) -> QueryResult<bool> {
+    diesel::select(diesel::dsl::exists(todo::dsl::todo.find(todo_id))).get_result(conn)
+}
+
+pub(crate) fn db_new_todo(conn: &SqliteConnection, t: &NewTodo) -> QueryResult<i32> {
+    // 在同一个事务中计算位置, 其他进程同时添加时不会得到相同的位置
+    retry_busy(|| conn.transaction::<_, diesel::result::Error, _>(|| {
+        let position = match t.expire_time {
+            Some(_) => 0,
+            None => db_next_undated_position(conn)?, // 没有日期的 todo 默认排在最后
+        };
+        diesel::insert_into(todo::table).values((t, todo::dsl::position.eq(position))).execute(conn)?;
+        diesel::select(last_insert_rowid).get_result::<i32>(conn)
+    }))
+}
+
+pub(crate) fn db_next_undated_position(conn: &SqliteConnection) -> QueryResult<i32> {
+    use diesel::expression::dsl::max;
+
+    let position: Option<i32> = todo::dsl::todo.select(max(todo::dsl::position)).filter(todo::dsl::expire_time.is_null()).first(conn)?;
+    Ok(position.unwrap_or(0) + 1)
+}
+
+pub(crate) fn db_find_todo(conn: &SqliteConnection, date: Option<Date<Local>>, list_id: Option<i32>) -> QueryResult<Vec<Todo>> {
+    let mut query = todo::dsl::todo.into_boxed();
+    if let Some(list_id) = list_id {
+        query = query.filter(todo::dsl::list_id.eq(list_id));
+    }
+
+    match date {
+        Some(date) => {
+            // 全天的 todo 不做时区转换, 所以先按前后各一天粗略筛选, 再用 is_on_date 精确过滤
+            let date = date.naive_local();
+            let time_start = date.pred().and_hms(0, 0, 0);
+            let time_end = date.succ().succ().and_hms(0, 0, 0);
+
+            let start_before_end = todo::dsl::start_time.is_null().and(todo::dsl::expire_time.lt(time_end)).or(todo::dsl::start_time.lt(time_end));
+            // 全天的排在前面
+            let todos = query.filter(todo::dsl::expire_time.ge(time_start).and(start_before_end))
+                .order_by((todo::dsl::position, todo::dsl::all_day.desc(), todo::dsl::expire_time)).load::<Todo>(conn)?;
+            Ok(todos.into_iter().filter(|todo| todo.is_on_date(date)).collect())
+        }
+        None => {
+            let now = Utc::now().naive_utc();
+            let todos = query.filter(todo::dsl::expire_time.is_null()).order_by((todo::dsl::position, todo::dsl::id)).load::<Todo>(conn)?;
+            Ok(todos.into_iter().filter(|todo| !todo.is_hidden(&now)).collect())
+        }
+    }
+}
+
+pub(crate) fn db_get_todo(conn: &SqliteConnection, todo_id: i32) -> QueryResult<Option<Todo>> {
+    todo::dsl::todo.find(todo_id).first::<Todo>(conn).optional()
+}
+
+pub(crate) fn db_all_todo(conn: &SqliteConnection) -> QueryResult<Vec<Todo>> {
+    todo::dsl::todo.order_by(todo::dsl::id).load::<Todo>(conn)
+}
+
+pub(crate) fn db_del_todo(conn: &SqliteConnection, todo_id: &[i32]) -> QueryResult<()> {
+    if todo_id.is_empty() {
+        return Ok(());
+    }
+    // 旧版本打开的连接没有开启外键检查, ON DELETE CASCADE 不一定生效, 手动删除提醒和附件
+    retry_busy(|| conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::delete(alarm::table.filter(alarm::todo_id.eq_any(todo_id))).execute(conn)?;
+        diesel::delete(attachment::table.filter(attachment::todo_id.eq_any(todo_id))).execute(conn)?;
+        diesel::delete(todo::table.filter(todo::id.eq_any(todo_id))).execute(conn)?;
+        Ok(())
+    }))
+}
+
+pub(crate) fn db_update_todo(conn: &SqliteConnection, todo: &Todo) -> QueryResult<()> {
+    retry_busy(|| diesel::update(
+        todo::table.filter(todo::dsl::id.eq(todo.id))
+    ).set((todo::dsl::content.eq(&todo.content), todo::dsl::expire_time.eq(&todo.expire_time), todo::dsl::position.eq(todo.position), todo::dsl::timezone.eq(&todo.timezone), todo::dsl::all_day.eq(todo.all_day),
+        todo::dsl::start_time.eq(&todo.start_time), todo::dsl::hide_until_start.eq(todo.hide_until_start),
+        todo::dsl::notes.eq(&todo.notes), todo::dsl::list_id.eq(todo.list_id), todo::dsl::completed.eq(todo.completed), todo::dsl::priority.eq(todo.priority)))
+        .execute(conn)).map(|_| ())
+}
+
+pub(crate) fn db_set_completed(conn: &SqliteConnection, todo_id: i32, completed: bool) -> QueryResult<()> {
+    retry_busy(|| diesel::update(todo::table.filter(todo::dsl::id.eq(todo_id))).set(todo::dsl::completed.eq(completed)).execute(conn)).map(|_| ())
+}
+
+// 按 todo_id 的顺序重新设置 position, 新建的 todo position 为 0, 会排在最前面
+pub(crate) fn db_reorder_todo(conn: &SqliteConnection, todo_id: &[i32]) -> QueryResult<()> {
+    retry_busy(|| conn.transaction::<_, diesel::result::Error, _>(|| {
+        for (position, id) in todo_id.iter().enumerate() {
+            diesel::update(todo::table.filter(todo::dsl::id.eq(id))).set(todo::dsl::position.eq(position as i32 + 1)).execute(conn)?;
+        }
+        Ok(())
+    }))
+}
+
+pub(crate) fn db_all_list(conn: &SqliteConnection) -> QueryResult<Vec<TodoList>> {
+    todo_list::dsl::todo_list.order_by(todo_list::dsl::id).load::<TodoList>(conn)
+}
+
+pub(crate) fn db_new_list(conn: &SqliteConnection, l: &NewTodoList) -> QueryResult<i32> {
+    retry_busy(|| conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::insert_into(todo_list::table).values(l).execute(conn)?;
+        diesel::select(last_insert_rowid).get_result::<i32>(conn)
+    }))
+}
+
+pub(crate) fn db_update_list(conn: &SqliteConnection, l: &TodoList) -> QueryResult<()> {
+    retry_busy(|| diesel::update(todo_list::table.filter(todo_list::dsl::id.eq(l.id)))
+        .set((todo_list::dsl::name.eq(&l.name), todo_list::dsl::color.eq(&l.color)))
+        .execute(conn)).map(|_| ())
+}
+
+// 清单中的 todo 不会被删除, 只是不再属于任何清单
+pub(crate) fn db_del_list(conn: &SqliteConnection, list_id: i32) -> QueryResult<()> {
+    retry_busy(|| conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::update(todo::table.filter(todo::dsl::list_id.eq(list_id))).set(todo::dsl::list_id.eq(None::<i32>)).execute(conn)?;
+        diesel::delete(todo_list::table.filter(todo_list::dsl::id.eq(list_id))).execute(conn)?;
+        Ok(())
+    }))
+}
+
+pub(crate) fn db_find_alarm(conn: &SqliteConnection, todo_id: i32) -> QueryResult<Vec<Alarm>> {
+    alarm::dsl::alarm.filter(alarm::dsl::todo_id.eq(todo_id)).order_by(alarm::dsl::id).load::<Alarm>(conn)
+}
+
+pub(crate) fn db_all_alarm(conn: &SqliteConnection) -> QueryResult<Vec<Alarm>> {
+    alarm::dsl::alarm.order_by(alarm::dsl::id).load::<Alarm>(conn)
+}
+
+// 和 TodoRepository 的默认实现结果相同, 但只查询一次
+pub(crate) fn db_find_due_alarm(conn: &SqliteConnection, from: NaiveDateTime, to: NaiveDateTime) -> QueryResult<Vec<(Todo, Alarm)>> {
+    let rows = alarm::table.inner_join(todo::table)
+        .filter(todo::dsl::completed.eq(false))
+        .order_by(alarm::dsl::id)
+        .select((todo::all_columns, alarm::all_columns))
+        .load::<(Todo, Alarm)>(conn)?;
+    Ok(rows.into_iter().filter(|(todo, alarm)| alarm.fire_time(todo).is_some_and(|time| time > from && time <= to)).collect())
+}
+
+pub(crate) fn db_set_alarm(conn: &SqliteConnection, todo_id: i32, alarms: &[NewAlarm]) -> QueryResult<()> {
+    retry_busy(|| conn.transaction::<_, diesel::result::Error, _>(|| {
+        if !todo_exists(conn, todo_id)? {
+            return Ok(());
+        }
+        diesel::delete(alarm::table.filter(alarm::dsl::todo_id.eq(todo_id))).execute(conn)?;
+        for a in alarms.iter() {
+            diesel::insert_into(alarm::table).values((a, alarm::dsl::todo_id.eq(todo_id))).execute(conn)?;
+        }
+        Ok(())
+    }))
+}
+
+pub(crate) fn db_find_attachment(conn: &SqliteConnection, todo_id: i32) -> QueryResult<Vec<Attachment>> {
+    attachment::dsl::attachment.filter(attachment::dsl::todo_id.eq(todo_id)).order_by(attachment::dsl::id).load::<Attachment>(conn)
+}
+
+pub(crate) fn db_all_attachment(conn: &SqliteConnection) -> QueryResult<Vec<Attachment>> {
+    attachment::dsl::attachment.order_by(attachment::dsl::id).load::<Attachment>(conn)
+}
+
+pub(crate) fn db_add_attachment(conn: &SqliteConnection, todo_id: i32, a: &NewAttachment) -> QueryResult<()> {
+    retry_busy(|| conn.transaction::<_, diesel::result::Error, _>(|| {
+        if todo_exists(conn, todo_id)? {
+            diesel::insert_into(attachment::table).values((a, attachment::dsl::todo_id.eq(todo_id))).execute(conn)?;
+        }
+        Ok(())
+    }))
+}
+
+pub(crate) fn db_set_attachment(conn: &SqliteConnection, todo_id: i32, attachments: &[NewAttachment]) -> QueryResult<()> {
+    retry_busy(|| conn.transaction::<_, diesel::result::Error, _>(|| {
+        if !todo_exists(conn, todo_id)? {
+            return Ok(());
+        }
+        diesel::delete(attachment::table.filter(attachment::dsl::todo_id.eq(todo_id))).execute(conn)?;
+        for a in attachments.iter() {
+            diesel::insert_into(attachment::table).values((a, attachment::dsl::todo_id.eq(todo_id))).execute(conn)?;
+        }
+        Ok(())
+    }))
+}
+
+pub(crate) fn db_get_exists_day(conn: &SqliteConnection, year: i32, month: i32, list_id: Option<i32>) -> QueryResult<Vec<i32>> {
+    let month_start = NaiveDate::from_ymd(year, month as u32, 1);
+
+    let month_end = if month < 12 {
+        NaiveDate::from_ymd(year, (month + 1) as u32, 1)
+    } else {
+        NaiveDate::from_ymd(year + 1, 1, 1)
+    };
+
+    // 和 db_find_todo 一样先粗略筛选, 跨多天的 todo 在 todo_days_in_month 里展开
+    let time_start = month_start.pred().and_hms(0, 0, 0);
+    let time_end = month_end.succ().and_hms(0, 0, 0);
+    let start_before_end = todo::dsl::start_time.is_null().and(todo::dsl::expire_time.lt(time_end)).or(todo::dsl::start_time.lt(time_end));
+    let mut query = todo::dsl::todo.filter(todo::dsl::expire_time.ge(time_start).and(start_before_end)).into_boxed();
+    if let Some(list_id) = list_id {
+        query = query.filter(todo::dsl::list_id.eq(list_id));
+    }
+    let todos = query.load::<Todo>(conn)?;
+
+    Ok(todo_days_in_month(&todos, year, month))
+}
+
+#[derive(QueryableByName)]
+struct DataVersion {
+    #[sql_type = "diesel::sql_types::BigInt"]
+    data_version: i64,
+}
+
+// 其他连接 (例如其他进程) 提交修改后会变化, 本连接自己的修改不会改变它
+pub(crate) fn db_data_version(conn: &SqliteConnection) -> QueryResult<i64> {
+    diesel::sql_query("PRAGMA data_version").get_result::<DataVersion>(conn).map(|version| version.data_version)
+}
+
+// 打开数据库失败的原因, 只在启动和切换数据库时出现
+#[derive(Debug)]
+pub enum OpenError {
+    NoHome,
+    CreateDir(String, std::io::Error),
+    Connect(String, ConnectionError),
+    Setup(diesel::result::Error),
+    Migration(diesel_migrations::RunMigrationsError),
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OpenError::NoHome => write!(f, "$HOME is not set"),
+            OpenError::CreateDir(path, e) => write!(f, "error creating {}: {}", path, e),
+            OpenError::Connect(path, e) => write!(f, "error connecting to {}: {}", path, e),
+            OpenError::Setup(e) => write!(f, "error setting up database: {}", e),
+            OpenError::Migration(e) => write!(f, "error migrating database: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for OpenError {}
+
+diesel_migrations::embed_migrations!("migrations/");
+pub(crate) fn establish_connection(database_path: Option<String>) -> Result<SqliteConnection, OpenError> {
+    let mut real_database_path = database_path.unwrap_or_else(|| String::from("~/.config/rust-reminder/todo.db"));
+
+    if real_database_path.starts_with('~') {
+        real_database_path = std::env::var("HOME").map_err(|_| OpenError::NoHome)? + &real_database_path[1..];
+    }
+
+    let real_database_path = path::Path::new(&real_database_path);
+    let display_path = real_database_path.to_string_lossy().to_string();
+    if let Some(parent) = real_database_path.parent() {
+        create_dir_all(parent).map_err(|e| OpenError::CreateDir(parent.to_string_lossy().to_string(), e))?;
+    }
+    let conn = SqliteConnection::establish(&display_path).map_err(|e| OpenError::Connect(display_path.clone(), e))?;
+
+    // WAL 模式下读写互不阻塞, 多个进程同时写入时最多等待 BUSY_TIMEOUT_MS
+    conn.batch_execute(&format!("PRAGMA busy_timeout = {}; PRAGMA journal_mode = WAL;", BUSY_TIMEOUT_MS)).map_err(OpenError::Setup)?;
+    embedded_migrations::run(&conn).map_err(OpenError::Migration)?;
+    // 迁移中可能会重建表, 迁移完成后再开启外键检查
+    conn.batch_execute("PRAGMA foreign_keys = ON;").map_err(OpenError::Setup)?;
+    Ok(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+    use super::{Todo, PRIORITY_NONE};
+
+    fn time(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(y, m, d).and_hms(h, min, 0)
+    }
+
+    fn get_todo(expire_time: Option<NaiveDateTime>, start_time: Option<NaiveDateTime>, timezone: Option<&str>) -> Todo {
+        Todo {
+            id: 1,
+            content: String::from("standup"),
+            expire_time,
+            position: 3,
+            timezone: timezone.map(String::from),
+            all_day: false,
+            start_time,
+            hide_until_start: false,
+            notes: String::new(),
+            list_id: None,
+            completed: false,
+            priority: PRIORITY_NONE,
+        }
+    }
+
+    #[test]
+    fn move_across_dst_keeps_wall_time() {
+        // 纽约 2026-11-01 结束夏令时, 9:00 EDT 为 13:00 UTC, 9:00 EST 为 14:00 UTC
+        let todo = get_todo(Some(time(2026, 10, 30, 13, 0)), Some(time(2026, 10, 29, 13, 0)), Some("America/New_York"));
+        let date = todo.local_date().unwrap() + Duration::days(3);
+
+        let moved = todo.moved_to_date(Some(date));
+        assert_eq!(moved.expire_time, Some(time(2026, 11, 2, 14, 0)));
+        assert_eq!(moved.wall_expire_time(), Some(time(2026, 11, 2, 9, 0)));
+        assert_eq!(moved.start_time, Some(time(2026, 11, 1, 14, 0)));
+        assert_eq!(moved.timezone.as_deref(), Some("America/New_York"));
+        assert!(!moved.all_day);
+    }
+
+    #[test]
+    fn move_to_and_from_undated() {
+        let undated = get_todo(None, None, None);
+        let moved = undated.moved_to_date(Some(NaiveDate::from_ymd(2026, 10, 20)));
+        assert_eq!(moved.expire_time, Some(time(2026, 10, 20, 0, 0)));
+        assert!(moved.all_day);
+
+        let moved = moved.moved_to_date(None);
+        assert_eq!(moved.expire_time, None);
+        assert!(!moved.all_day);
+    }
+}
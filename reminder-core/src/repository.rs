@@ -0,0 +1,609 @@
+use std::collections::HashMap;
+
+use chrono::{Date, Local, NaiveDateTime};
+
+use crate::models::{Alarm, Attachment, NewAlarm, NewAttachment, NewTodo, NewTodoList, Todo, TodoList};
+
+// 读写失败时返回 diesel 的错误, 调用方决定是提示用户还是只输出日志
+pub type StoreError = diesel::result::Error;
+pub type StoreResult<T> = Result<T, StoreError>;
+
+// 存储后端, GUI 只通过这个 trait 读写 todo, 方便替换存储和测试
+pub trait TodoRepository {
+    // 返回新 todo 的 id
+    fn new_todo(&self, todo: &NewTodo) -> StoreResult<i32>;
+
+    fn next_undated_position(&self) -> StoreResult<i32>;
+
+    // date 为 None 时返回没有日期的 todo, list_id 为 None 时返回所有清单中的 todo
+    fn find_todo(&self, date: Option<Date<Local>>, list_id: Option<i32>) -> StoreResult<Vec<Todo>>;
+
+    fn get_todo(&self, todo_id: i32) -> StoreResult<Option<Todo>>;
+
+    fn all_todo(&self) -> StoreResult<Vec<Todo>>;
+
+    fn del_todo(&self, todo_id: &[i32]) -> StoreResult<()>;
+
+    fn update_todo(&self, todo: &Todo) -> StoreResult<()>;
+
+    // 只修改完成状态, 不会覆盖其他进程同时对 todo 做的修改
+    fn set_completed(&self, todo_id: i32, completed: bool) -> StoreResult<()>;
+
+    fn reorder_todo(&self, todo_id: &[i32]) -> StoreResult<()>;
+
+    // 返回该月中有 todo 的日期 (升序, 不重复), month 从 1 开始, list_id 和 find_todo 相同
+    fn get_exists_day(&self, year: i32, month: i32, list_id: Option<i32>) -> StoreResult<Vec<i32>>;
+
+    fn all_list(&self) -> StoreResult<Vec<TodoList>>;
+
+    // 返回新清单的 id
+    fn new_list(&self, list: &NewTodoList) -> StoreResult<i32>;
+
+    fn update_list(&self, list: &TodoList) -> StoreResult<()>;
+
+    // 清单中的 todo 会保留下来, 不再属于任何清单
+    fn del_list(&self, list_id: i32) -> StoreResult<()>;
+
+    fn find_alarm(&self, todo_id: i32) -> StoreResult<Vec<Alarm>>;
+
+    fn all_alarm(&self) -> StoreResult<Vec<Alarm>>;
+
+    // 用 alarms 替换该 todo 原有的提醒, 删除 todo 时提醒也会一起删除
+    fn set_alarm(&self, todo_id: i32, alarms: &[NewAlarm]) -> StoreResult<()>;
+
+    fn find_attachment(&self, todo_id: i32) -> StoreResult<Vec<Attachment>>;
+
+    fn all_attachment(&self) -> StoreResult<Vec<Attachment>>;
+
+    fn add_attachment(&self, todo_id: i32, attachment: &NewAttachment) -> StoreResult<()>;
+
+    // 和 set_alarm 一样整体替换
+    fn set_attachment(&self, todo_id: i32, attachments: &[NewAttachment]) -> StoreResult<()>;
+
+    // 其他程序修改了存储后这个值会变化, 用于判断是否需要重新读取
+    fn data_version(&self) -> StoreResult<i64>;
+
+    // 触发时间在 (from, to] 之间的提醒和它所属的 todo, 时间都是 UTC, 已完成的 todo 不会提醒
+    fn find_due_alarm(&self, from: NaiveDateTime, to: NaiveDateTime) -> StoreResult<Vec<(Todo, Alarm)>> {
+        let todos: HashMap<i32, Todo> = self.all_todo()?.into_iter().map(|todo| (todo.id, todo)).collect();
+        let mut due_alarms = Vec::new();
+        for alarm in self.all_alarm()?.into_iter() {
+            let todo = match todos.get(&alarm.todo_id) {
+                Some(todo) if !todo.completed => todo.clone(),
+                _ => continue,
+            };
+            match alarm.fire_time(&todo) {
+                Some(time) if time > from && time <= to => due_alarms.push((todo, alarm)),
+                _ => {}
+            }
+        }
+        Ok(due_alarms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+    use super::TodoRepository;
+    use crate::{MemoryTodoStore, NewAlarm, NewAttachment, NewTodo, NewTodoList, OpenError, TodoStore, PRIORITY_HIGH, PRIORITY_LOW, wall_to_utc};
+
+    fn time(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> Option<NaiveDateTime> {
+        Some(NaiveDate::from_ymd(y, m, d).and_hms(h, min, s))
+    }
+
+    // expire_time 为本地时间
+    fn add(repo: &dyn TodoRepository, content: &str, expire_time: Option<NaiveDateTime>) -> i32 {
+        repo.new_todo(&NewTodo {
+            content: String::from(content),
+            expire_time: expire_time.map(|t| wall_to_utc(&t, None)),
+            timezone: None,
+            all_day: false,
+            start_time: None,
+            hide_until_start: false,
+            notes: String::new(),
+            list_id: None,
+            priority: 0,
+        }).unwrap()
+    }
+
+    fn add_all_day(repo: &dyn TodoRepository, content: &str, y: i32, m: u32, d: u32) -> i32 {
+        repo.new_todo(&NewTodo {
+            content: String::from(content),
+            expire_time: time(y, m, d, 0, 0, 0),
+            timezone: None,
+            all_day: true,
+            start_time: None,
+            hide_until_start: false,
+            notes: String::new(),
+            list_id: None,
+            priority: 0,
+        }).unwrap()
+    }
+
+    // start 和 expire_time 一样为本地时间
+    fn add_span(repo: &dyn TodoRepository, content: &str, start: Option<NaiveDateTime>, expire_time: Option<NaiveDateTime>, hide_until_start: bool) {
+        repo.new_todo(&NewTodo {
+            content: String::from(content),
+            expire_time: expire_time.map(|t| wall_to_utc(&t, None)),
+            timezone: None,
+            all_day: false,
+            start_time: start.map(|t| wall_to_utc(&t, None)),
+            hide_until_start,
+            notes: String::new(),
+            list_id: None,
+            priority: 0,
+        }).unwrap();
+    }
+
+    fn contents(repo: &dyn TodoRepository, date: Option<(i32, u32, u32)>) -> Vec<String> {
+        let date = date.map(|(y, m, d)| Local.ymd(y, m, d));
+        repo.find_todo(date, None).unwrap().into_iter().map(|todo| todo.content).collect()
+    }
+
+    // 两种实现跑同一套测试, 保证行为一致
+    fn for_each_repo(check: fn(&dyn TodoRepository)) {
+        check(&MemoryTodoStore::new());
+        check(&TodoStore::open(Some(String::from(":memory:"))).unwrap());
+    }
+
+    #[test]
+    fn find_by_date_includes_whole_day() {
+        for_each_repo(|repo| {
+            add(repo, "before", time(2026, 10, 13, 23, 59, 59));
+            add(repo, "start", time(2026, 10, 14, 0, 0, 0));
+            add(repo, "end", time(2026, 10, 14, 23, 59, 59));
+            add(repo, "after", time(2026, 10, 15, 0, 0, 0));
+            add(repo, "undated", None);
+
+            assert_eq!(contents(repo, Some((2026, 10, 14))), vec!["start", "end"]);
+        });
+    }
+
+    #[test]
+    fn find_by_date_orders_by_time() {
+        for_each_repo(|repo| {
+            add(repo, "afternoon", time(2026, 10, 14, 15, 0, 0));
+            add(repo, "morning", time(2026, 10, 14, 9, 0, 0));
+
+            assert_eq!(contents(repo, Some((2026, 10, 14))), vec!["morning", "afternoon"]);
+        });
+    }
+
+    #[test]
+    fn find_undated() {
+        for_each_repo(|repo| {
+            add(repo, "a", None);
+            add(repo, "dated", time(2026, 10, 14, 9, 0, 0));
+            add(repo, "b", None);
+
+            assert_eq!(contents(repo, None), vec!["a", "b"]);
+        });
+    }
+
+    #[test]
+    fn reorder_keeps_order_and_appends_new_undated() {
+        for_each_repo(|repo| {
+            add(repo, "a", None);
+            add(repo, "b", None);
+            add(repo, "c", None);
+
+            let mut todo_id: Vec<i32> = repo.find_todo(None, None).unwrap().iter().map(|todo| todo.id).collect();
+            todo_id.reverse();
+            repo.reorder_todo(&todo_id).unwrap();
+            add(repo, "d", None);
+
+            assert_eq!(contents(repo, None), vec!["c", "b", "a", "d"]);
+        });
+    }
+
+    #[test]
+    fn update_and_delete() {
+        for_each_repo(|repo| {
+            add(repo, "a", time(2026, 10, 14, 9, 0, 0));
+            add(repo, "b", time(2026, 10, 14, 10, 0, 0));
+
+            let mut todo = repo.find_todo(Some(Local.ymd(2026, 10, 14)), None).unwrap()[0].clone();
+            todo.content = String::from("a2");
+            todo.expire_time = None;
+            repo.update_todo(&todo).unwrap();
+            assert_eq!(repo.get_todo(todo.id).unwrap().unwrap().content, "a2");
+            assert_eq!(contents(repo, None), vec!["a2"]);
+
+            repo.del_todo(&[todo.id]).unwrap();
+            assert!(repo.get_todo(todo.id).unwrap().is_none());
+            assert_eq!(repo.all_todo().unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn exists_day_stays_within_month() {
+        for_each_repo(|repo| {
+            add(repo, "last day of previous month", time(2026, 9, 30, 23, 59, 59));
+            add(repo, "first", time(2026, 10, 1, 0, 0, 0));
+            add(repo, "twice", time(2026, 10, 14, 9, 0, 0));
+            add(repo, "twice", time(2026, 10, 14, 18, 0, 0));
+            add(repo, "last", time(2026, 10, 31, 23, 59, 59));
+            add(repo, "first of next month", time(2026, 11, 1, 0, 0, 0));
+            add(repo, "undated", None);
+
+            assert_eq!(repo.get_exists_day(2026, 10, None).unwrap(), vec![1, 14, 31]);
+            assert_eq!(repo.get_exists_day(2026, 11, None).unwrap(), vec![1]);
+            assert_eq!(repo.get_exists_day(2026, 9, None).unwrap(), vec![30]);
+        });
+    }
+
+    #[test]
+    fn all_day_listed_first_on_its_date() {
+        for_each_repo(|repo| {
+            add(repo, "timed", time(2026, 10, 14, 0, 0, 0));
+            add_all_day(repo, "all day", 2026, 10, 14);
+            add_all_day(repo, "next day", 2026, 10, 15);
+
+            assert_eq!(contents(repo, Some((2026, 10, 14))), vec!["all day", "timed"]);
+            assert_eq!(contents(repo, Some((2026, 10, 15))), vec!["next day"]);
+            assert_eq!(repo.find_todo(Some(Local.ymd(2026, 10, 15)), None).unwrap()[0].local_date(), Some(NaiveDate::from_ymd(2026, 10, 15)));
+        });
+    }
+
+    #[test]
+    fn exists_day_includes_all_day() {
+        for_each_repo(|repo| {
+            add_all_day(repo, "first", 2026, 10, 1);
+            add_all_day(repo, "last", 2026, 10, 31);
+            add_all_day(repo, "next month", 2026, 11, 1);
+            add(repo, "timed", time(2026, 10, 14, 9, 0, 0));
+
+            assert_eq!(repo.get_exists_day(2026, 10, None).unwrap(), vec![1, 14, 31]);
+            assert_eq!(repo.get_exists_day(2026, 11, None).unwrap(), vec![1]);
+        });
+    }
+
+    #[test]
+    fn span_listed_on_every_day() {
+        for_each_repo(|repo| {
+            add_span(repo, "report", time(2026, 10, 12, 9, 0, 0), time(2026, 10, 16, 17, 0, 0), false);
+            add_span(repo, "meeting", time(2026, 10, 14, 14, 0, 0), time(2026, 10, 14, 15, 30, 0), false);
+
+            assert_eq!(contents(repo, Some((2026, 10, 11))), Vec::<String>::new());
+            assert_eq!(contents(repo, Some((2026, 10, 12))), vec!["report"]);
+            assert_eq!(contents(repo, Some((2026, 10, 14))), vec!["meeting", "report"]);
+            assert_eq!(contents(repo, Some((2026, 10, 16))), vec!["report"]);
+            assert_eq!(contents(repo, Some((2026, 10, 17))), Vec::<String>::new());
+        });
+    }
+
+    #[test]
+    fn span_marks_days_across_months() {
+        for_each_repo(|repo| {
+            add_span(repo, "trip", time(2026, 10, 30, 9, 0, 0), time(2026, 11, 2, 18, 0, 0), false);
+            repo.new_todo(&NewTodo {
+                content: String::from("holiday"),
+                expire_time: time(2026, 12, 2, 0, 0, 0),
+                timezone: None,
+                all_day: true,
+                start_time: time(2026, 11, 30, 0, 0, 0),
+                hide_until_start: false,
+                notes: String::new(),
+                list_id: None,
+                priority: 0,
+            }).unwrap();
+
+            assert_eq!(repo.get_exists_day(2026, 10, None).unwrap(), vec![30, 31]);
+            assert_eq!(repo.get_exists_day(2026, 11, None).unwrap(), vec![1, 2, 30]);
+            assert_eq!(repo.get_exists_day(2026, 12, None).unwrap(), vec![1, 2]);
+        });
+    }
+
+    #[test]
+    fn undated_hidden_until_start() {
+        for_each_repo(|repo| {
+            let now = Local::now().naive_local();
+            add_span(repo, "later", Some(now + Duration::days(1)), None, true);
+            add_span(repo, "later but shown", Some(now + Duration::days(1)), None, false);
+            add_span(repo, "started", Some(now - Duration::days(1)), None, true);
+
+            assert_eq!(contents(repo, None), vec!["later but shown", "started"]);
+        });
+    }
+
+    #[test]
+    fn exists_day_across_year_boundary() {
+        for_each_repo(|repo| {
+            add(repo, "new year's eve", time(2026, 12, 31, 23, 0, 0));
+            add(repo, "new year", time(2027, 1, 1, 0, 0, 0));
+
+            assert_eq!(repo.get_exists_day(2026, 12, None).unwrap(), vec![31]);
+            assert_eq!(repo.get_exists_day(2027, 1, None).unwrap(), vec![1]);
+        });
+    }
+
+    #[test]
+    fn exists_day_in_leap_february() {
+        for_each_repo(|repo| {
+            add(repo, "leap day", time(2028, 2, 29, 12, 0, 0));
+            add(repo, "march", time(2028, 3, 1, 0, 0, 0));
+
+            assert_eq!(repo.get_exists_day(2028, 2, None).unwrap(), vec![29]);
+            assert_eq!(repo.get_exists_day(2028, 3, None).unwrap(), vec![1]);
+        });
+    }
+
+    #[test]
+    fn new_todo_returns_id() {
+        for_each_repo(|repo| {
+            let first = add(repo, "first", None);
+            let second = add(repo, "second", time(2026, 10, 14, 9, 0, 0));
+
+            assert_ne!(first, second);
+            assert_eq!(repo.get_todo(second).unwrap().unwrap().content, "second");
+        });
+    }
+
+    #[test]
+    fn alarms_replaced_and_deleted_with_todo() {
+        for_each_repo(|repo| {
+            let todo_id = add(repo, "meeting", time(2026, 10, 14, 9, 0, 0));
+            let other_id = add(repo, "other", time(2026, 10, 14, 10, 0, 0));
+            let before = |minutes| NewAlarm { trigger_time: None, trigger_offset: Some(minutes) };
+
+            repo.set_alarm(todo_id, &[before(15), before(24 * 60)]).unwrap();
+            repo.set_alarm(other_id, &[before(5)]).unwrap();
+            let offsets = |id| repo.find_alarm(id).unwrap().iter().map(|a| a.trigger_offset.unwrap()).collect::<Vec<i32>>();
+            assert_eq!(offsets(todo_id), vec![15, 24 * 60]);
+
+            repo.set_alarm(todo_id, &[before(60)]).unwrap();
+            assert_eq!(offsets(todo_id), vec![60]);
+
+            repo.del_todo(&[todo_id]).unwrap();
+            assert!(repo.find_alarm(todo_id).unwrap().is_empty());
+            assert_eq!(repo.all_alarm().unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn alarms_and_attachments_of_deleted_todo_are_ignored() {
+        for_each_repo(|repo| {
+            let todo_id = add(repo, "deleted elsewhere", time(2026, 10, 14, 9, 0, 0));
+            repo.del_todo(&[todo_id]).unwrap();
+
+            repo.set_alarm(todo_id, &[NewAlarm { trigger_time: None, trigger_offset: Some(15) }]).unwrap();
+            repo.add_attachment(todo_id, &NewAttachment { uri: String::from("file:///tmp/a.txt") }).unwrap();
+            repo.set_attachment(todo_id, &[NewAttachment { uri: String::from("file:///tmp/b.txt") }]).unwrap();
+            assert!(repo.all_alarm().unwrap().is_empty());
+            assert!(repo.all_attachment().unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn alarm_fire_time() {
+        for_each_repo(|repo| {
+            let timed = add(repo, "timed", time(2026, 10, 14, 9, 0, 0));
+            let all_day = add_all_day(repo, "all day", 2026, 10, 14);
+            let undated = add(repo, "undated", None);
+            let absolute = time(2026, 10, 13, 20, 0, 0).map(|t| wall_to_utc(&t, None));
+            for id in [timed, all_day, undated] {
+                repo.set_alarm(id, &[
+                    NewAlarm { trigger_time: None, trigger_offset: Some(15) },
+                    NewAlarm { trigger_time: absolute, trigger_offset: None },
+                ]).unwrap();
+            }
+
+            let fire_times = |id| {
+                let todo = repo.get_todo(id).unwrap().unwrap();
+                repo.find_alarm(id).unwrap().iter().map(|a| a.fire_time(&todo)).collect::<Vec<Option<NaiveDateTime>>>()
+            };
+            assert_eq!(fire_times(timed), vec![time(2026, 10, 14, 8, 45, 0).map(|t| wall_to_utc(&t, None)), absolute]);
+            // 全天的 todo 相对当天 00:00 提醒
+            assert_eq!(fire_times(all_day), vec![time(2026, 10, 13, 23, 45, 0).map(|t| wall_to_utc(&t, None)), absolute]);
+            assert_eq!(fire_times(undated), vec![None, absolute]);
+        });
+    }
+
+    #[test]
+    fn find_due_alarm_in_range() {
+        for_each_repo(|repo| {
+            let timed = add(repo, "timed", time(2026, 10, 14, 9, 0, 0));
+            let undated = add(repo, "undated", None);
+            repo.set_alarm(timed, &[NewAlarm { trigger_time: None, trigger_offset: Some(15) }]).unwrap();
+            repo.set_alarm(undated, &[NewAlarm { trigger_time: None, trigger_offset: Some(15) }]).unwrap();
+
+            let due = |from, to| {
+                let utc = |t: Option<NaiveDateTime>| wall_to_utc(&t.unwrap(), None);
+                repo.find_due_alarm(utc(from), utc(to)).unwrap().into_iter().map(|(todo, _)| todo.content).collect::<Vec<String>>()
+            };
+            assert_eq!(due(time(2026, 10, 14, 8, 44, 0), time(2026, 10, 14, 8, 45, 0)), vec!["timed"]);
+            // 上一次检查时已经提醒过了
+            assert!(due(time(2026, 10, 14, 8, 45, 0), time(2026, 10, 14, 8, 46, 0)).is_empty());
+            assert!(due(time(2026, 10, 14, 8, 0, 0), time(2026, 10, 14, 8, 44, 59)).is_empty());
+        });
+    }
+
+    #[test]
+    fn completed_todo_kept_without_alarm() {
+        for_each_repo(|repo| {
+            let todo_id = add(repo, "done", time(2026, 10, 14, 9, 0, 0));
+            repo.set_alarm(todo_id, &[NewAlarm { trigger_time: None, trigger_offset: Some(15) }]).unwrap();
+            assert!(!repo.get_todo(todo_id).unwrap().unwrap().completed);
+
+            repo.set_completed(todo_id, true).unwrap();
+            assert!(repo.get_todo(todo_id).unwrap().unwrap().completed);
+            assert_eq!(contents(repo, Some((2026, 10, 14))), vec!["done"]);
+            let from = wall_to_utc(&time(2026, 10, 14, 8, 0, 0).unwrap(), None);
+            let to = wall_to_utc(&time(2026, 10, 14, 9, 0, 0).unwrap(), None);
+            assert!(repo.find_due_alarm(from, to).unwrap().is_empty());
+
+            // update_todo 也会保存完成状态
+            let mut todo = repo.get_todo(todo_id).unwrap().unwrap();
+            todo.completed = false;
+            repo.update_todo(&todo).unwrap();
+            assert!(!repo.get_todo(todo_id).unwrap().unwrap().completed);
+            assert_eq!(repo.find_due_alarm(from, to).unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn notes_and_priority_stored_with_todo() {
+        for_each_repo(|repo| {
+            let todo_id = repo.new_todo(&NewTodo {
+                content: String::from("trip"),
+                expire_time: None,
+                timezone: None,
+                all_day: false,
+                start_time: None,
+                hide_until_start: false,
+                notes: String::from("passport\ntickets"),
+                list_id: None,
+                priority: PRIORITY_HIGH,
+            }).unwrap();
+            assert_eq!(repo.get_todo(todo_id).unwrap().unwrap().notes, "passport\ntickets");
+            assert_eq!(repo.get_todo(todo_id).unwrap().unwrap().priority, PRIORITY_HIGH);
+
+            let mut todo = repo.get_todo(todo_id).unwrap().unwrap();
+            todo.notes = String::new();
+            todo.priority = PRIORITY_LOW;
+            repo.update_todo(&todo).unwrap();
+            assert_eq!(repo.get_todo(todo_id).unwrap().unwrap().notes, "");
+            assert_eq!(repo.get_todo(todo_id).unwrap().unwrap().priority, PRIORITY_LOW);
+            assert_eq!(contents(repo, None), vec!["trip"]);
+        });
+    }
+
+    #[test]
+    fn attachments_added_replaced_and_deleted_with_todo() {
+        for_each_repo(|repo| {
+            let todo_id = add(repo, "report", None);
+            let other_id = add(repo, "other", None);
+            let attachment = |uri: &str| NewAttachment { uri: String::from(uri) };
+            let uris = |id| repo.find_attachment(id).unwrap().into_iter().map(|a| a.uri).collect::<Vec<String>>();
+
+            repo.add_attachment(todo_id, &attachment("file:///tmp/report.pdf")).unwrap();
+            repo.add_attachment(todo_id, &attachment("https://example.com/issue/1")).unwrap();
+            repo.add_attachment(other_id, &attachment("file:///tmp/other.txt")).unwrap();
+            assert_eq!(uris(todo_id), vec!["file:///tmp/report.pdf", "https://example.com/issue/1"]);
+
+            repo.set_attachment(todo_id, &[attachment("https://example.com/issue/1")]).unwrap();
+            assert_eq!(uris(todo_id), vec!["https://example.com/issue/1"]);
+
+            repo.del_todo(&[todo_id]).unwrap();
+            assert!(uris(todo_id).is_empty());
+            assert_eq!(repo.all_attachment().unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn find_by_list() {
+        for_each_repo(|repo| {
+            let work = repo.new_list(&NewTodoList { name: String::from("Work"), color: String::from("#3584e4") }).unwrap();
+            let home = repo.new_list(&NewTodoList { name: String::from("Home"), color: String::from("#e66100") }).unwrap();
+            let add_to = |content: &str, expire_time, list_id| {
+                let todo_id = add(repo, content, expire_time);
+                let mut todo = repo.get_todo(todo_id).unwrap().unwrap();
+                todo.list_id = list_id;
+                repo.update_todo(&todo).unwrap();
+            };
+            add_to("report", time(2026, 10, 14, 9, 0, 0), Some(work));
+            add_to("laundry", time(2026, 10, 15, 9, 0, 0), Some(home));
+            add_to("no list", time(2026, 10, 16, 9, 0, 0), None);
+            add_to("someday", None, Some(home));
+            let list_contents = |date: Option<(i32, u32, u32)>, list_id| {
+                repo.find_todo(date.map(|(y, m, d)| Local.ymd(y, m, d)), list_id).unwrap().into_iter().map(|todo| todo.content).collect::<Vec<String>>()
+            };
+
+            assert_eq!(list_contents(Some((2026, 10, 14)), Some(work)), vec!["report"]);
+            assert_eq!(list_contents(Some((2026, 10, 14)), Some(home)), Vec::<String>::new());
+            assert_eq!(list_contents(None, Some(home)), vec!["someday"]);
+            assert_eq!(repo.get_exists_day(2026, 10, Some(home)).unwrap(), vec![15]);
+            assert_eq!(repo.get_exists_day(2026, 10, None).unwrap(), vec![14, 15, 16]);
+
+            // 删除清单后 todo 还在, 只是不再属于任何清单
+            repo.del_list(home).unwrap();
+            assert_eq!(repo.all_list().unwrap().iter().map(|l| l.name.clone()).collect::<Vec<String>>(), vec!["Work"]);
+            assert_eq!(list_contents(None, None), vec!["someday"]);
+            assert_eq!(repo.find_todo(None, None).unwrap()[0].list_id, None);
+        });
+    }
+
+    #[test]
+    fn data_version_changes_after_external_write() {
+        let path = std::env::temp_dir().join(format!("reminder-data-version-{}.db", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let store = TodoStore::open(Some(path.clone())).unwrap();
+        let other = TodoStore::open(Some(path.clone())).unwrap();
+
+        let version = store.data_version().unwrap();
+        add(&store, "own write", None);
+        assert_eq!(store.data_version().unwrap(), version);
+        add(&other, "other write", None);
+        assert_ne!(store.data_version().unwrap(), version);
+        assert_eq!(contents(&store, None), vec!["own write", "other write"]);
+
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path, suffix));
+        }
+    }
+
+    const HAMMER_DB_ENV: &str = "REMINDER_HAMMER_DB";
+    const HAMMER_COUNT: usize = 200;
+
+    // 每轮新建一个 todo 和提醒, 删除其中一半, 每个进程最后留下 HAMMER_COUNT / 2 个
+    fn hammer(path: &str, name: &str) {
+        let store = TodoStore::open(Some(path.to_string())).unwrap();
+        for i in 0..HAMMER_COUNT {
+            let todo_id = add(&store, &format!("{} {}", name, i), None);
+            store.set_alarm(todo_id, &[NewAlarm { trigger_time: None, trigger_offset: Some(15) }]).unwrap();
+            store.find_todo(None, None).unwrap();
+            if i % 2 == 1 {
+                store.del_todo(&[todo_id]).unwrap();
+            }
+        }
+    }
+
+    // 由 concurrent_writes_from_two_processes 在子进程中运行
+    #[test]
+    fn open_errors_are_returned() {
+        let file = std::env::temp_dir().join(format!("reminder-not-a-db-{}", std::process::id()));
+        std::fs::write(&file, "not a database, just some text that is long enough to be read as a header").unwrap();
+
+        // 父目录是普通文件时无法创建目录, 不是 sqlite 的文件在设置 PRAGMA 时出错
+        let under_file = file.join("todo.db").to_str().unwrap().to_string();
+        assert!(matches!(TodoStore::open(Some(under_file)), Err(OpenError::CreateDir(..))));
+        assert!(matches!(TodoStore::open(Some(file.to_str().unwrap().to_string())), Err(OpenError::Setup(_))));
+
+        let _ = std::fs::remove_file(&file);
+    }
+
+    #[test]
+    #[ignore]
+    fn hammer_child() {
+        if let Ok(path) = std::env::var(HAMMER_DB_ENV) {
+            hammer(&path, "child");
+        }
+    }
+
+    #[test]
+    fn concurrent_writes_from_two_processes() {
+        let path = std::env::temp_dir().join(format!("reminder-hammer-{}.db", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        TodoStore::open(Some(path.clone())).unwrap(); // 先完成迁移
+
+        let child = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--ignored", "--exact", "repository::tests::hammer_child"])
+            .env(HAMMER_DB_ENV, &path)
+            .stdout(std::process::Stdio::piped())
+            .spawn().unwrap();
+        hammer(&path, "parent");
+        let child = child.wait_with_output().unwrap();
+        assert!(child.status.success(), "{}", String::from_utf8_lossy(&child.stdout));
+
+        let store = TodoStore::open(Some(path.clone())).unwrap();
+        let todos = store.all_todo().unwrap();
+        assert_eq!(todos.iter().filter(|todo| todo.content.starts_with("parent")).count(), HAMMER_COUNT / 2);
+        assert_eq!(todos.iter().filter(|todo| todo.content.starts_with("child")).count(), HAMMER_COUNT / 2);
+        assert_eq!(store.all_alarm().unwrap().len(), HAMMER_COUNT);
+
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path, suffix));
+        }
+    }
+}
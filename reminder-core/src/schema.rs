@@ -0,0 +1,51 @@
+table! {
+    todo(id) {
+        id -> Integer,
+        content -> Text,
+        expire_time -> Nullable<Timestamp>,
+        position -> Integer,
+        timezone -> Nullable<Text>,
+        all_day -> Bool,
+        start_time -> Nullable<Timestamp>,
+        hide_until_start -> Bool,
+        notes -> Text,
+        list_id -> Nullable<Integer>,
+        completed -> Bool,
+        priority -> Integer,
+    }
+}
+
+table! {
+    todo_list(id) {
+        id -> Integer,
+        name -> Text,
+        color -> Text,
+    }
+}
+
+table! {
+    alarm(id) {
+        id -> Integer,
+        todo_id -> Integer,
+        trigger_time -> Nullable<Timestamp>,
+        trigger_offset -> Nullable<Integer>,
+    }
+}
+
+table! {
+    attachment(id) {
+        id -> Integer,
+        todo_id -> Integer,
+        uri -> Text,
+    }
+}
+
+joinable!(alarm -> todo (todo_id));
+joinable!(attachment -> todo (todo_id));
+
+allow_tables_to_appear_in_same_query!(
+    alarm,
+    attachment,
+    todo,
+    todo_list,
+);
\ No newline at end of file
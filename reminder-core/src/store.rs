@@ -0,0 +1,113 @@
+use chrono::{Date, Local, NaiveDateTime};
+use diesel::SqliteConnection;
+
+use crate::models::{Alarm, Attachment, db_add_attachment, db_all_alarm, db_all_attachment, db_all_list, db_all_todo, db_data_version, db_del_list, db_del_todo, db_find_alarm, db_find_attachment, db_find_due_alarm, db_find_todo, db_get_exists_day, db_get_todo, db_new_list, db_new_todo, db_next_undated_position, db_reorder_todo, db_set_alarm, db_set_attachment, db_set_completed, db_update_list, db_update_todo, establish_connection, NewAlarm, OpenError, NewAttachment, NewTodo, NewTodoList, Todo, TodoList};
+use crate::repository::{StoreResult, TodoRepository};
+
+// 基于 sqlite 的存储
+pub struct TodoStore {
+    conn: SqliteConnection,
+}
+
+impl TodoStore {
+    // database_path 为 None 时使用默认的 ~/.config/rust-reminder/todo.db
+    pub fn open(database_path: Option<String>) -> Result<TodoStore, OpenError> {
+        Ok(TodoStore {
+            conn: establish_connection(database_path)?,
+        })
+    }
+}
+
+impl TodoRepository for TodoStore {
+    fn new_todo(&self, todo: &NewTodo) -> StoreResult<i32> {
+        db_new_todo(&self.conn, todo)
+    }
+
+    fn next_undated_position(&self) -> StoreResult<i32> {
+        db_next_undated_position(&self.conn)
+    }
+
+    fn find_todo(&self, date: Option<Date<Local>>, list_id: Option<i32>) -> StoreResult<Vec<Todo>> {
+        db_find_todo(&self.conn, date, list_id)
+    }
+
+    fn get_todo(&self, todo_id: i32) -> StoreResult<Option<Todo>> {
+        db_get_todo(&self.conn, todo_id)
+    }
+
+    fn all_todo(&self) -> StoreResult<Vec<Todo>> {
+        db_all_todo(&self.conn)
+    }
+
+    fn del_todo(&self, todo_id: &[i32]) -> StoreResult<()> {
+        db_del_todo(&self.conn, todo_id)
+    }
+
+    fn update_todo(&self, todo: &Todo) -> StoreResult<()> {
+        db_update_todo(&self.conn, todo)
+    }
+
+    fn set_completed(&self, todo_id: i32, completed: bool) -> StoreResult<()> {
+        db_set_completed(&self.conn, todo_id, completed)
+    }
+
+    fn reorder_todo(&self, todo_id: &[i32]) -> StoreResult<()> {
+        db_reorder_todo(&self.conn, todo_id)
+    }
+
+    fn get_exists_day(&self, year: i32, month: i32, list_id: Option<i32>) -> StoreResult<Vec<i32>> {
+        db_get_exists_day(&self.conn, year, month, list_id)
+    }
+
+    fn all_list(&self) -> StoreResult<Vec<TodoList>> {
+        db_all_list(&self.conn)
+    }
+
+    fn new_list(&self, list: &NewTodoList) -> StoreResult<i32> {
+        db_new_list(&self.conn, list)
+    }
+
+    fn update_list(&self, list: &TodoList) -> StoreResult<()> {
+        db_update_list(&self.conn, list)
+    }
+
+    fn del_list(&self, list_id: i32) -> StoreResult<()> {
+        db_del_list(&self.conn, list_id)
+    }
+
+    fn find_alarm(&self, todo_id: i32) -> StoreResult<Vec<Alarm>> {
+        db_find_alarm(&self.conn, todo_id)
+    }
+
+    fn all_alarm(&self) -> StoreResult<Vec<Alarm>> {
+        db_all_alarm(&self.conn)
+    }
+
+    fn set_alarm(&self, todo_id: i32, alarms: &[NewAlarm]) -> StoreResult<()> {
+        db_set_alarm(&self.conn, todo_id, alarms)
+    }
+
+    fn find_attachment(&self, todo_id: i32) -> StoreResult<Vec<Attachment>> {
+        db_find_attachment(&self.conn, todo_id)
+    }
+
+    fn all_attachment(&self) -> StoreResult<Vec<Attachment>> {
+        db_all_attachment(&self.conn)
+    }
+
+    fn add_attachment(&self, todo_id: i32, attachment: &NewAttachment) -> StoreResult<()> {
+        db_add_attachment(&self.conn, todo_id, attachment)
+    }
+
+    fn set_attachment(&self, todo_id: i32, attachments: &[NewAttachment]) -> StoreResult<()> {
+        db_set_attachment(&self.conn, todo_id, attachments)
+    }
+
+    fn data_version(&self) -> StoreResult<i64> {
+        db_data_version(&self.conn)
+    }
+
+    fn find_due_alarm(&self, from: NaiveDateTime, to: NaiveDateTime) -> StoreResult<Vec<(Todo, Alarm)>> {
+        db_find_due_alarm(&self.conn, from, to)
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::{Date, DateTime, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, Offset, TimeZone};
+use chrono_tz::Tz;
+
+// 把某个时区的墙上时间转换为带时区的时间, 不会因为夏令时 panic
+pub fn resolve_local<T: TimeZone>(tz: &T, time: &NaiveDateTime) -> DateTime<T> {
+    match tz.from_local_datetime(time) {
+        LocalResult::Single(t) => t,
+        LocalResult::Ambiguous(earliest, _) => earliest, // 时钟回拨时重复的时间取第一次
+        LocalResult::None => {
+            // 时钟拨快时跳过的时间按跳变前的偏移换算, 会落到跳变之后, 例如 02:30 变成 03:30
+            let offset = tz.offset_from_utc_datetime(&(*time - Duration::days(1))).fix();
+            tz.from_utc_datetime(&(*time - Duration::seconds(offset.local_minus_utc() as i64)))
+        }
+    }
+}
+
+// IANA 时区名, 例如 Asia/Tokyo
+pub fn parse_timezone(name: &str) -> Option<Tz> {
+    name.parse::<Tz>().ok()
+}
+
+pub fn timezone_names() -> Vec<&'static str> {
+    chrono_tz::TZ_VARIANTS.iter().map(|tz| tz.name()).collect()
+}
+
+// timezone 为 None 或无法识别时按本地时区处理
+pub fn wall_to_utc(time: &NaiveDateTime, timezone: Option<&str>) -> NaiveDateTime {
+    match timezone.and_then(parse_timezone) {
+        Some(tz) => resolve_local(&tz, time).naive_utc(),
+        None => resolve_local(&Local, time).naive_utc(),
+    }
+}
+
+pub fn utc_to_wall(time: &NaiveDateTime, timezone: Option<&str>) -> NaiveDateTime {
+    match timezone.and_then(parse_timezone) {
+        Some(tz) => tz.from_utc_datetime(time).naive_local(),
+        None => Local.from_utc_datetime(time).naive_local(),
+    }
+}
+
+// 代替 Local.ymd, 当天 00:00 不存在时不会 panic
+pub fn local_date(date: NaiveDate) -> Date<Local> {
+    resolve_local(&Local, &date.and_hms(0, 0, 0)).date()
+}
+
+// 本地某一天开始时刻对应的 UTC 时间
+pub fn local_day_start(date: NaiveDate) -> NaiveDateTime {
+    wall_to_utc(&date.and_hms(0, 0, 0), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{parse_timezone, resolve_local, utc_to_wall, wall_to_utc};
+
+    fn time(y: i32, m: u32, d: u32, h: u32, min: u32) -> chrono::NaiveDateTime {
+        NaiveDate::from_ymd(y, m, d).and_hms(h, min, 0)
+    }
+
+    #[test]
+    fn named_timezone() {
+        let tokyo = Some("Asia/Tokyo");
+        assert_eq!(wall_to_utc(&time(2026, 10, 14, 9, 0), tokyo), time(2026, 10, 14, 0, 0));
+        assert_eq!(utc_to_wall(&time(2026, 10, 14, 0, 0), tokyo), time(2026, 10, 14, 9, 0));
+    }
+
+    #[test]
+    fn gap_moves_forward() {
+        // 2026-03-29 02:00 柏林时钟拨到 03:00
+        let berlin = parse_timezone("Europe/Berlin").unwrap();
+        let t = resolve_local(&berlin, &time(2026, 3, 29, 2, 30));
+        assert_eq!(t.naive_local(), time(2026, 3, 29, 3, 30));
+        assert_eq!(t.naive_utc(), time(2026, 3, 29, 1, 30));
+    }
+
+    #[test]
+    fn overlap_takes_earliest() {
+        // 2026-10-25 03:00 柏林时钟回拨到 02:00, 02:30 出现两次
+        let berlin = parse_timezone("Europe/Berlin").unwrap();
+        let t = resolve_local(&berlin, &time(2026, 10, 25, 2, 30));
+        assert_eq!(t.naive_utc(), time(2026, 10, 25, 0, 30));
+    }
+
+    #[test]
+    fn round_trip_outside_transitions() {
+        let time = time(2026, 7, 1, 9, 0);
+        for name in ["Europe/Berlin", "America/New_York", "Australia/Lord_Howe", "Asia/Kolkata"] {
+            assert_eq!(utc_to_wall(&wall_to_utc(&time, Some(name)), Some(name)), time);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use chrono::Utc;
+use gtk::{gio, glib};
+use gtk::prelude::*;
+
+use reminder_core::TodoRepository;
+
+use crate::settings::Settings;
+use crate::utils::get_todo_time_text;
+
+const CHECK_INTERVAL_SECONDS: u32 = 20;
+
+// 定时检查到期的提醒并发送桌面通知, 和窗口是否打开无关. 点击通知会触发 activate 打开窗口
+// store 在修改数据库路径后会被替换, 每次检查时重新取
+pub fn start_alarm_scheduler(application: &gtk::Application, store: Rc<RefCell<Rc<dyn TodoRepository>>>, settings: Rc<RefCell<Settings>>) {
+    let last_check = Cell::new(Utc::now().naive_utc()); // 启动之前错过的提醒不再补发
+    let application = application.clone();
+    glib::timeout_add_seconds_local(CHECK_INTERVAL_SECONDS, move || {
+        let now = Utc::now().naive_utc();
+        let due_alarms = match store.borrow().find_due_alarm(last_check.get(), now) {
+            Ok(due_alarms) => due_alarms,
+            Err(e) => {
+                eprintln!("Error checking alarms: {}", e);
+                return glib::Continue(true); // 不更新 last_check, 下次检查时补发
+            }
+        };
+        for (todo, alarm) in due_alarms {
+            let notification = gio::Notification::new(&todo.content);
+            notification.set_body(get_todo_time_text(&todo, settings.borrow().time_format).as_deref());
+            application.send_notification(Some(&format!("alarm-{}", alarm.id)), &notification);
+        }
+        last_check.set(now);
+        glib::Continue(true)
+    });
+}
@@ -0,0 +1,994 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
+
+// 会被一起去掉的介词, 例如 "at 3pm", "on friday"
+const PREPOSITIONS: &[&str] = &["at", "on", "by", "due"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedTodo {
+    pub content: String,
+    pub date: Option<NaiveDate>,
+    pub time: Option<NaiveTime>,
+    pub default_time: NaiveTime, // 只给了日期没给时间时用的时间, 来自设置
+}
+
+impl ParsedTodo {
+    pub fn expire_time(&self) -> Option<NaiveDateTime> {
+        self.date.map(|date| date.and_time(self.time.unwrap_or(self.default_time)))
+    }
+
+    // 只写了日期没写时间
+    pub fn is_all_day(&self) -> bool {
+        self.date.is_some() && self.time.is_none()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Meridiem {
+    Morning,
+    Noon,
+    Afternoon,
+    Evening,
+}
+
+#[derive(Default)]
+struct ParseState {
+    date: Option<NaiveDate>,
+    hour: Option<u32>,
+    minute: u32,
+    meridiem: Option<Meridiem>,
+    offset: Option<Duration>,
+    recurrence: Option<(NaiveDate, NaiveDate)>, // "every friday" 从今天开始的第一次和第二次
+}
+
+impl ParseState {
+    fn set_time(&mut self, hour: u32, minute: u32) {
+        self.hour = Some(hour);
+        self.minute = minute;
+    }
+
+    fn time(&self) -> Option<NaiveTime> {
+        let hour = match (self.hour, self.meridiem) {
+            (Some(hour), Some(Meridiem::Morning)) if hour == 12 => 0,
+            (Some(hour), Some(Meridiem::Noon)) if hour <= 2 => hour + 12,
+            (Some(hour), Some(Meridiem::Afternoon)) if hour < 12 => hour + 12,
+            (Some(hour), Some(Meridiem::Evening)) if (5..12).contains(&hour) => hour + 12,
+            (Some(12), Some(Meridiem::Evening)) => 0,
+            (Some(hour), _) => hour,
+            (None, Some(Meridiem::Morning)) => 9,
+            (None, Some(Meridiem::Noon)) => 12,
+            (None, Some(Meridiem::Afternoon)) => 15,
+            (None, Some(Meridiem::Evening)) => 20,
+            (None, None) => return None,
+        };
+        NaiveTime::from_hms_opt(hour, self.minute, 0)
+    }
+
+    // "晚上两点", "晚上十二点" 是过了午夜之后, 算第二天的
+    fn is_after_midnight(&self) -> bool {
+        self.meridiem == Some(Meridiem::Evening) && self.hour.map_or(false, |hour| hour < 5 || hour == 12)
+    }
+
+    fn resolve(self, content: String, now: NaiveDateTime, base_date: Option<NaiveDate>, default_time: NaiveTime) -> ParsedTodo {
+        let today = now.date();
+
+        if let Some(time) = self.offset.and_then(|offset| now.checked_add_signed(offset)) {
+            return ParsedTodo {
+                content: content,
+                date: Some(time.date()),
+                time: Some(NaiveTime::from_hms(time.hour(), time.minute(), 0)),
+                default_time: default_time,
+            };
+        }
+
+        let time = self.time();
+        let after_midnight = self.is_after_midnight();
+        let date = match (self.date, self.recurrence, time) {
+            (Some(date), _, _) => Some(date),
+            (None, Some((first, next)), Some(time)) if first == today && time <= now.time() && !after_midnight => Some(next), // 今天这次已经过了
+            (None, Some((first, _)), _) => Some(first),
+            (None, None, Some(_)) if base_date.is_some() => base_date,
+            (None, None, Some(time)) if time > now.time() || after_midnight => Some(today),
+            (None, None, Some(_)) => today.succ_opt(), // 时间已经过了, 算明天的
+            (None, None, None) => None,
+        };
+        let date = if after_midnight { date.and_then(|date| date.succ_opt()) } else { date };
+
+        ParsedTodo {
+            content: content,
+            date: date,
+            time: time,
+            default_time: default_time,
+        }
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd(next_year, next_month, 1).pred().day()
+}
+
+fn add_months(date: NaiveDate, months: u32) -> NaiveDate {
+    let month0 = date.month0() + months;
+    let year = date.year() + (month0 / 12) as i32;
+    let month = month0 % 12 + 1;
+    NaiveDate::from_ymd(year, month, date.day().min(days_in_month(year, month)))
+}
+
+// 从 date 开始 (包括 date) 的第一个 weekday
+fn upcoming_weekday(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (weekday.num_days_from_monday() + 7 - date.weekday().num_days_from_monday()) % 7;
+    date + Duration::days(days as i64)
+}
+
+// number 个 unit_seconds 秒, 加到 today 上超出日期范围时返回 None, 避免 Duration 和日期计算溢出 panic
+fn checked_offset(today: NaiveDate, number: u32, unit_seconds: i64) -> Option<Duration> {
+    let seconds = (number as i64).checked_mul(unit_seconds)?;
+    if seconds > i64::MAX / 1000 - 24 * 3600 {
+        return None;
+    }
+    let offset = Duration::seconds(seconds);
+    today.and_hms(0, 0, 0).checked_add_signed(offset + Duration::days(1))?; // 加上当天的时间后也不能超出范围
+    Some(offset)
+}
+
+fn checked_add_days(today: NaiveDate, number: u32, unit_days: i64) -> Option<NaiveDate> {
+    today.checked_add_signed(checked_offset(today, number, unit_days * 24 * 3600)?)
+}
+
+// 以 week_start 为一周的开始, offset_weeks 为 0 表示本周, 1 表示下周
+fn weekday_of_week(date: NaiveDate, weekday: Weekday, offset_weeks: i64, week_start: Weekday) -> NaiveDate {
+    let days_from_start = |weekday: Weekday| ((weekday.num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7) as i64;
+    let first_day = date - Duration::days(days_from_start(date.weekday()));
+    first_day + Duration::days(offset_weeks * 7 + days_from_start(weekday))
+}
+
+// 本月的 day 号, 已经过了的话就是下个月的
+fn upcoming_month_day(today: NaiveDate, day: u32) -> Option<NaiveDate> {
+    if day < 1 || day > 31 {
+        return None;
+    }
+    let this_month = NaiveDate::from_ymd(today.year(), today.month(), 1);
+    let month = if day >= today.day() { this_month } else { add_months(this_month, 1) };
+    Some(NaiveDate::from_ymd(month.year(), month.month(), day.min(days_in_month(month.year(), month.month()))))
+}
+
+// 今年的 month 月 day 日, 已经过了的话就是明年的
+fn upcoming_date(today: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    if date >= today {
+        Some(date)
+    } else {
+        NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+    }
+}
+
+fn normalize_word(word: &str) -> String {
+    word.trim_end_matches(|c| ",.;!?，。；！？".contains(c)).to_lowercase()
+}
+
+fn parse_weekday(word: &str, allow_abbreviation: bool) -> Option<Weekday> {
+    let weekday = match word {
+        "monday" => Weekday::Mon,
+        "tuesday" => Weekday::Tue,
+        "wednesday" => Weekday::Wed,
+        "thursday" => Weekday::Thu,
+        "friday" => Weekday::Fri,
+        "saturday" => Weekday::Sat,
+        "sunday" => Weekday::Sun,
+        _ if !allow_abbreviation => return None,
+        "mon" => Weekday::Mon,
+        "tue" | "tues" => Weekday::Tue,
+        "wed" => Weekday::Wed,
+        "thu" | "thur" | "thurs" => Weekday::Thu,
+        "fri" => Weekday::Fri,
+        "sat" => Weekday::Sat,
+        "sun" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+fn parse_month(word: &str) -> Option<u32> {
+    let month = match word {
+        "january" | "jan" => 1,
+        "february" | "feb" => 2,
+        "march" | "mar" => 3,
+        "april" | "apr" => 4,
+        "may" => 5,
+        "june" | "jun" => 6,
+        "july" | "jul" => 7,
+        "august" | "aug" => 8,
+        "september" | "sep" | "sept" => 9,
+        "october" | "oct" => 10,
+        "november" | "nov" => 11,
+        "december" | "dec" => 12,
+        _ => return None,
+    };
+    Some(month)
+}
+
+fn parse_english_number(word: &str) -> Option<u32> {
+    let number = match word {
+        "a" | "an" | "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        "ten" => 10,
+        _ => return word.parse::<u32>().ok(),
+    };
+    Some(number)
+}
+
+fn parse_day_of_month(word: &str) -> Option<u32> {
+    let day = word.trim_end_matches(|c: char| c.is_ascii_alphabetic()); // 1st, 2nd, 3rd, 20th
+    if day.is_empty() || (day.len() != word.len() && !["st", "nd", "rd", "th"].contains(&&word[day.len()..])) {
+        return None;
+    }
+    day.parse::<u32>().ok().filter(|day| (1..=31).contains(day))
+}
+
+fn parse_meridiem(word: &str) -> Option<Meridiem> {
+    match word {
+        "am" | "a.m" => Some(Meridiem::Morning),
+        "pm" | "p.m" => Some(Meridiem::Afternoon),
+        _ => None,
+    }
+}
+
+// 15:30, 3pm, 3:30pm
+fn parse_clock(word: &str) -> Option<(u32, u32, Option<Meridiem>)> {
+    let (clock, meridiem) = match ["am", "a.m", "pm", "p.m"].iter().find(|suffix| word.ends_with(*suffix)) {
+        Some(suffix) => (&word[..word.len() - suffix.len()], parse_meridiem(suffix)),
+        None => (word, None),
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        Some(_) => return None,
+        None if meridiem.is_some() => (clock.parse::<u32>().ok()?, 0),
+        None => return None, // 单独的数字不当作时间
+    };
+
+    if hour > 23 || minute > 59 || (meridiem.is_some() && (hour == 0 || hour > 12)) {
+        return None;
+    }
+    Some((hour, minute, meridiem))
+}
+
+// 返回匹配掉的单词数, 0 表示没有匹配
+fn match_english(words: &[String], state: &mut ParseState, today: NaiveDate) -> usize {
+    let word = words[0].as_str();
+    let next = words.get(1).map(|w| w.as_str());
+
+    if word == "day" && next == Some("after") && words.get(2).map(|w| w.as_str()) == Some("tomorrow") {
+        state.date = Some(today + Duration::days(2));
+        return 3;
+    }
+
+    match word {
+        "today" => state.date = Some(today),
+        "tonight" => {
+            state.date = Some(today);
+            state.meridiem = Some(Meridiem::Evening);
+        }
+        "tomorrow" | "tmr" | "tmrw" => state.date = Some(today + Duration::days(1)),
+        "noon" => state.set_time(12, 0),
+        "midnight" => state.set_time(0, 0),
+        _ => return match_english_pair(words, state, today),
+    }
+    return 1;
+}
+
+fn match_english_pair(words: &[String], state: &mut ParseState, today: NaiveDate) -> usize {
+    let word = words[0].as_str();
+    let next = words.get(1).map(|w| w.as_str());
+
+    if let (Some(weekday), true) = (next.and_then(|next| parse_weekday(next, true)), ["next", "this"].contains(&word)) {
+        match word {
+            "next" => state.date = Some(upcoming_weekday(today + Duration::days(1), weekday)),
+            _ => state.date = Some(upcoming_weekday(today, weekday)),
+        }
+        return 2;
+    }
+
+    match (word, next) {
+        ("next", Some("week")) => state.date = Some(today + Duration::days(7)),
+        ("next", Some("month")) => state.date = Some(add_months(today, 1)),
+        _ => return match_english_datetime(words, state, today),
+    }
+    return 2;
+}
+
+fn match_english_datetime(words: &[String], state: &mut ParseState, today: NaiveDate) -> usize {
+    let word = words[0].as_str();
+    let next = words.get(1).map(|w| w.as_str());
+
+    if let Some(weekday) = parse_weekday(word, false) {
+        state.date = Some(upcoming_weekday(today, weekday));
+        return 1;
+    }
+
+    if let (true, Some(number), Some(unit)) = (word == "in", next.and_then(parse_english_number), words.get(2)) {
+        let (date, offset) = match unit.as_str() {
+            "minute" | "minutes" | "min" | "mins" => (None, checked_offset(today, number, 60)),
+            "hour" | "hours" | "hr" | "hrs" => (None, checked_offset(today, number, 3600)),
+            "day" | "days" => (checked_add_days(today, number, 1), None),
+            "week" | "weeks" => (checked_add_days(today, number, 7), None),
+            _ => return 0,
+        };
+        if date.is_none() && offset.is_none() {
+            return 0; // 超出日期范围时当作普通文字
+        }
+        state.date = date.or(state.date);
+        state.offset = offset.or(state.offset);
+        return 3;
+    }
+
+    for format in ["%Y-%m-%d", "%Y/%m/%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(word, format) {
+            state.date = Some(date);
+            return 1;
+        }
+    }
+
+    // oct 20th, 20 october
+    let month_day = match (parse_month(word), next.and_then(parse_day_of_month)) {
+        (Some(month), Some(day)) => Some((month, day)),
+        _ => match (parse_day_of_month(word), next.and_then(parse_month)) {
+            (Some(day), Some(month)) => Some((month, day)),
+            _ => None,
+        },
+    };
+    if let Some(date) = month_day.and_then(|(month, day)| upcoming_date(today, month, day)) {
+        state.date = Some(date);
+        return 2;
+    }
+
+    // 3 pm, 3:30 pm
+    if let Some(meridiem) = next.and_then(parse_meridiem) {
+        if let Some((hour, minute, _)) = parse_clock(&format!("{}{}", word, next.unwrap())) {
+            state.set_time(hour, minute);
+            state.meridiem = Some(meridiem);
+            return 2;
+        }
+    }
+
+    if let Some((hour, minute, meridiem)) = parse_clock(word) {
+        state.set_time(hour, minute);
+        state.meridiem = meridiem.or(state.meridiem);
+        return 1;
+    }
+
+    return 0;
+}
+
+fn is_cjk(c: char) -> bool {
+    ('\u{4e00}'..='\u{9fff}').contains(&c)
+}
+
+fn starts_with(chars: &[char], prefix: &str) -> Option<usize> {
+    let prefix: Vec<char> = prefix.chars().collect();
+    if chars.len() >= prefix.len() && chars[..prefix.len()] == prefix[..] {
+        Some(prefix.len())
+    } else {
+        None
+    }
+}
+
+fn starts_with_any(chars: &[char], prefixes: &[&str]) -> Option<usize> {
+    prefixes.iter().find_map(|prefix| starts_with(chars, prefix))
+}
+
+fn chinese_digit(c: char) -> Option<u32> {
+    "零一二三四五六七八九".chars().position(|d| d == c).map(|d| d as u32).or(match c {
+        '〇' => Some(0),
+        '两' => Some(2),
+        _ => None,
+    })
+}
+
+// 开头连续的阿拉伯数字或中文数字的长度
+fn number_len(chars: &[char]) -> usize {
+    match chars.iter().take_while(|c| c.is_ascii_digit()).count() {
+        0 => chars.iter().take_while(|c| **c == '十' || chinese_digit(**c).is_some()).count(),
+        digits => digits,
+    }
+}
+
+// 逐位拼起来, 例如 "二三" 为 23, 太长溢出时返回 None
+fn fold_chinese_digits(digits: &[char]) -> Option<u32> {
+    digits.iter().try_fold(0u32, |acc, c| acc.checked_mul(10)?.checked_add(chinese_digit(*c).unwrap_or(0)))
+}
+
+// 支持阿拉伯数字和 "十五", "二十三" 这种中文数字, 返回 (数值, 占用的字符数)
+fn parse_chinese_number(chars: &[char]) -> Option<(u32, usize)> {
+    let len = number_len(chars);
+    if len == 0 {
+        return None;
+    }
+    if chars[0].is_ascii_digit() {
+        let number: String = chars[..len].iter().collect();
+        return number.parse::<u32>().ok().map(|number| (number, len));
+    }
+
+    let number = &chars[..len];
+    let value = match number.iter().position(|c| *c == '十') {
+        Some(pos) => {
+            let tens = if pos == 0 { 1 } else { fold_chinese_digits(&number[..pos])? };
+            let units = fold_chinese_digits(&number[pos + 1..])?;
+            tens.checked_mul(10)?.checked_add(units)?
+        }
+        None => fold_chinese_digits(number)?,
+    };
+    Some((value, len))
+}
+
+fn parse_chinese_weekday(c: Option<&char>) -> Option<Weekday> {
+    match c? {
+        '一' | '1' => Some(Weekday::Mon),
+        '二' | '2' => Some(Weekday::Tue),
+        '三' | '3' => Some(Weekday::Wed),
+        '四' | '4' => Some(Weekday::Thu),
+        '五' | '5' => Some(Weekday::Fri),
+        '六' | '6' => Some(Weekday::Sat),
+        '日' | '天' | '七' | '7' => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+// 返回匹配掉的字符数, 0 表示没有匹配
+fn match_chinese(chars: &[char], state: &mut ParseState, today: NaiveDate, week_start: Weekday) -> usize {
+    for (word, days, meridiem) in [
+        ("大后天", 3, None), ("后天", 2, None), ("明天", 1, None), ("今天", 0, None),
+        ("今晚", 0, Some(Meridiem::Evening)), ("明晚", 1, Some(Meridiem::Evening)), ("明早", 1, Some(Meridiem::Morning)),
+    ] {
+        if let Some(len) = starts_with(chars, word) {
+            state.date = Some(today + Duration::days(days));
+            state.meridiem = meridiem.or(state.meridiem);
+            return len;
+        }
+    }
+
+    for (word, meridiem) in [
+        ("上午", Meridiem::Morning), ("早上", Meridiem::Morning), ("早晨", Meridiem::Morning), ("凌晨", Meridiem::Morning),
+        ("中午", Meridiem::Noon), ("下午", Meridiem::Afternoon), ("傍晚", Meridiem::Evening), ("晚上", Meridiem::Evening),
+    ] {
+        if let Some(len) = starts_with(chars, word) {
+            state.meridiem = Some(meridiem);
+            return len;
+        }
+    }
+
+    if let Some(len) = starts_with_any(chars, &["下个星期", "下星期", "下礼拜", "下周"]) {
+        return match parse_chinese_weekday(chars.get(len)) {
+            Some(weekday) => {
+                state.date = Some(weekday_of_week(today, weekday, 1, week_start));
+                len + 1
+            }
+            None => {
+                state.date = Some(today + Duration::days(7));
+                len
+            }
+        };
+    }
+    if let Some(len) = starts_with_any(chars, &["这个星期", "这星期", "这周", "本周"]) {
+        if let Some(weekday) = parse_chinese_weekday(chars.get(len)) {
+            state.date = Some(weekday_of_week(today, weekday, 0, week_start));
+            return len + 1;
+        }
+    }
+    if let Some(len) = starts_with_any(chars, &["星期", "礼拜", "周"]) {
+        if let Some(weekday) = parse_chinese_weekday(chars.get(len)) {
+            state.date = Some(upcoming_weekday(today, weekday));
+            return len + 1;
+        }
+    }
+
+    if let Some(len) = starts_with_any(chars, &["半个小时", "半小时"]) {
+        if let Some(suffix_len) = starts_with_any(&chars[len..], &["以后", "之后", "后"]) {
+            state.offset = Some(Duration::minutes(30));
+            return len + suffix_len;
+        }
+    }
+
+    match parse_chinese_number(chars) {
+        Some((number, len)) => match_chinese_number(number, len, chars, state, today),
+        None => 0,
+    }
+}
+
+// 以数字开头的: 3天后, 10月20日, 20号, 下午三点半, 15:30
+fn match_chinese_number(number: u32, len: usize, chars: &[char], state: &mut ParseState, today: NaiveDate) -> usize {
+    let rest = &chars[len..];
+
+    if let Some(unit_len) = starts_with_any(rest, &["天", "周", "个星期", "个小时", "小时", "分钟"]) {
+        if let Some(suffix_len) = starts_with_any(&rest[unit_len..], &["以后", "之后", "后"]) {
+            let unit: String = rest[..unit_len].iter().collect();
+            let (date, offset) = match unit.as_str() {
+                "天" => (checked_add_days(today, number, 1), None),
+                "周" | "个星期" => (checked_add_days(today, number, 7), None),
+                "个小时" | "小时" => (None, checked_offset(today, number, 3600)),
+                _ => (None, checked_offset(today, number, 60)),
+            };
+            if date.is_none() && offset.is_none() {
+                return 0;
+            }
+            state.date = date.or(state.date);
+            state.offset = offset.or(state.offset);
+            return len + unit_len + suffix_len;
+        }
+    }
+
+    if let Some(month_len) = starts_with(rest, "月") {
+        if let Some((day, day_len)) = parse_chinese_number(&rest[month_len..]) {
+            if let Some(suffix_len) = starts_with_any(&rest[month_len + day_len..], &["日", "号"]) {
+                if let Some(date) = upcoming_date(today, number, day) {
+                    state.date = Some(date);
+                    return len + month_len + day_len + suffix_len;
+                }
+            }
+        }
+        return 0;
+    }
+
+    if let Some(suffix_len) = starts_with_any(rest, &["日", "号"]) {
+        if let Some(date) = upcoming_month_day(today, number) {
+            state.date = Some(date);
+            return len + suffix_len;
+        }
+        return 0;
+    }
+
+    if let (Some(hour_len), true) = (starts_with_any(rest, &["点", "时"]), number <= 23) {
+        let rest = &rest[hour_len..];
+        let (minute, minute_len) = if let Some(minute_len) = starts_with(rest, "半") {
+            (30, minute_len)
+        } else if let Some(minute_len) = starts_with(rest, "一刻") {
+            (15, minute_len)
+        } else if let Some(minute_len) = starts_with(rest, "三刻") {
+            (45, minute_len)
+        } else if let Some(minute_len) = starts_with(rest, "整") {
+            (0, minute_len)
+        } else {
+            match parse_chinese_number(rest) {
+                Some((minute, minute_len)) if minute < 60 => (minute, minute_len + starts_with(&rest[minute_len..], "分").unwrap_or(0)),
+                _ => (0, 0),
+            }
+        };
+        state.set_time(number, minute);
+        return len + hour_len + minute_len;
+    }
+
+    if let (Some(colon_len), true) = (starts_with_any(rest, &[":", "："]), number <= 23) {
+        let minute: String = rest[colon_len..].iter().take_while(|c| c.is_ascii_digit()).collect();
+        if let (2, Ok(minute)) = (minute.len(), minute.parse::<u32>()) {
+            if minute < 60 {
+                state.set_time(number, minute);
+                return len + colon_len + 2;
+            }
+        }
+    }
+
+    return 0;
+}
+
+fn weekly_recurrence(today: NaiveDate, weekday: Weekday) -> (NaiveDate, NaiveDate) {
+    let first = upcoming_weekday(today, weekday);
+    (first, first + Duration::days(7))
+}
+
+// 目前不支持重复的 todo, "每周五", "每月1号" 这样的说法原样留在内容里, 到期时间为下一次, 例如最近的周五
+fn match_chinese_recurrence(chars: &[char], state: &mut ParseState, today: NaiveDate) -> usize {
+    if let Some(len) = starts_with_any(chars, &["每天", "每日"]) {
+        return len;
+    }
+    if let Some(len) = starts_with_any(chars, &["每个星期", "每星期", "每周"]) {
+        return match parse_chinese_weekday(chars.get(len)) {
+            Some(weekday) => {
+                state.recurrence = Some(weekly_recurrence(today, weekday));
+                len + 1
+            }
+            None => len,
+        };
+    }
+    if let Some(len) = starts_with_any(chars, &["每个月", "每月"]) {
+        if let Some((day, day_len)) = parse_chinese_number(&chars[len..]) {
+            if let Some(suffix_len) = starts_with_any(&chars[len + day_len..], &["号", "日"]) {
+                let first = upcoming_month_day(today, day);
+                state.recurrence = first.zip(first.and_then(|first| upcoming_month_day(first.succ_opt()?, day)));
+                return len + day_len + suffix_len;
+            }
+        }
+        return len;
+    }
+    return 0;
+}
+
+// 和 match_chinese_recurrence 一样, "every friday", "daily" 原样留在内容里
+fn match_english_recurrence(words: &[String], state: &mut ParseState, today: NaiveDate) -> usize {
+    let next = words.get(1).map(|w| w.as_str());
+    match words[0].as_str() {
+        "daily" | "weekly" | "monthly" => 1,
+        "every" => match next {
+            Some(next) if ["day", "week", "month"].contains(&next) => 2,
+            Some(next) => match parse_weekday(next, true) {
+                Some(weekday) => {
+                    state.recurrence = Some(weekly_recurrence(today, weekday));
+                    2
+                }
+                None => 0,
+            },
+            None => 0,
+        },
+        _ => 0,
+    }
+}
+
+fn parse_chinese(word: &str, state: &mut ParseState, today: NaiveDate, week_start: Weekday) -> String {
+    let chars: Vec<char> = word.chars().collect();
+    let mut rest = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let recurrence_len = match_chinese_recurrence(&chars[i..], state, today);
+        if recurrence_len > 0 {
+            rest.extend(&chars[i..i + recurrence_len]);
+            i += recurrence_len;
+            continue;
+        }
+        match match_chinese(&chars[i..], state, today, week_start) {
+            0 => {
+                // 没匹配上的数字整个跳过, 否则会从数字中间开始重新解析, 例如把 "99999天后" 当作 "9999天后"
+                let len = number_len(&chars[i..]).max(1);
+                rest.extend(&chars[i..i + len]);
+                i += len;
+            }
+            len => i += len,
+        }
+    }
+    return rest;
+}
+
+// 从 "call bob tomorrow 3pm", "明天下午三点开会" 这样的文本里解析出时间, content 为去掉时间后剩下的部分
+// week_start 和 default_time 来自设置
+pub fn parse_todo(text: &str, now: NaiveDateTime, week_start: Weekday, default_time: NaiveTime) -> ParsedTodo {
+    return parse_todo_on(text, now, None, week_start, default_time);
+}
+
+// 同 parse_todo, 但只写了时间没写日期时使用 base_date 而不是今天或明天
+pub fn parse_todo_on(text: &str, now: NaiveDateTime, base_date: Option<NaiveDate>, week_start: Weekday, default_time: NaiveTime) -> ParsedTodo {
+    let today = now.date();
+    let mut state = ParseState::default();
+    let mut content: Vec<String> = Vec::new();
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let normalized_words: Vec<String> = words.iter().map(|w| normalize_word(w)).collect();
+
+    let mut i = 0;
+    while i < words.len() {
+        let recurrence_len = match_english_recurrence(&normalized_words[i..], &mut state, today);
+        if recurrence_len > 0 {
+            content.extend(words[i..i + recurrence_len].iter().map(|w| String::from(*w)));
+            i += recurrence_len;
+            continue;
+        }
+
+        let mut consumed = match_english(&normalized_words[i..], &mut state, today);
+        if consumed == 0 && i + 1 < words.len() && PREPOSITIONS.contains(&normalized_words[i].as_str()) {
+            consumed = match_english(&normalized_words[i + 1..], &mut state, today);
+            if consumed > 0 {
+                consumed += 1;
+            }
+        }
+
+        if consumed > 0 {
+            i += consumed;
+            continue;
+        }
+
+        if words[i].chars().any(is_cjk) {
+            let rest = parse_chinese(words[i], &mut state, today, week_start);
+            if !rest.is_empty() {
+                content.push(rest);
+            }
+        } else {
+            content.push(String::from(words[i]));
+        }
+        i += 1;
+    }
+
+    return state.resolve(content.join(" "), now, base_date, default_time);
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+
+    use super::{parse_todo, parse_todo_on, ParsedTodo};
+
+    // 2026-10-14 是周三
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd(2026, 10, 14).and_hms(10, 30, 0)
+    }
+
+    fn nine() -> NaiveTime {
+        NaiveTime::from_hms(9, 0, 0)
+    }
+
+    fn parse(text: &str) -> ParsedTodo {
+        parse_todo(text, now(), Weekday::Mon, nine())
+    }
+
+    fn at(month: u32, day: u32, hour: u32, minute: u32) -> Option<NaiveDateTime> {
+        Some(NaiveDate::from_ymd(2026, month, day).and_hms(hour, minute, 0))
+    }
+
+    #[test]
+    fn plain_text_has_no_time() {
+        let parsed = parse("buy 3 apples");
+        assert_eq!(parsed.content, "buy 3 apples");
+        assert_eq!(parsed.expire_time(), None);
+    }
+
+    #[test]
+    fn time_only_uses_base_date() {
+        let base_date = NaiveDate::from_ymd(2026, 10, 20);
+        let parsed = parse_todo_on("standup 9am", now(), Some(base_date), Weekday::Mon, nine());
+        assert_eq!(parsed.content, "standup");
+        assert_eq!(parsed.expire_time(), at(10, 20, 9, 0));
+
+        // 明确写了日期或相对时间时不使用 base_date
+        let parsed = parse_todo_on("standup tomorrow 9am", now(), Some(base_date), Weekday::Mon, nine());
+        assert_eq!(parsed.expire_time(), at(10, 15, 9, 0));
+        let parsed = parse_todo_on("standup in 2 hours", now(), Some(base_date), Weekday::Mon, nine());
+        assert_eq!(parsed.expire_time(), at(10, 14, 12, 30));
+        let parsed = parse_todo_on("buy milk", now(), Some(base_date), Weekday::Mon, nine());
+        assert_eq!(parsed.expire_time(), None);
+    }
+
+    #[test]
+    fn tomorrow_with_meridiem() {
+        let parsed = parse("call bob tomorrow 3pm");
+        assert_eq!(parsed.content, "call bob");
+        assert_eq!(parsed.expire_time(), at(10, 15, 15, 0));
+    }
+
+    #[test]
+    fn separated_meridiem_and_preposition() {
+        let parsed = parse("call bob tomorrow at 3:30 pm");
+        assert_eq!(parsed.content, "call bob");
+        assert_eq!(parsed.expire_time(), at(10, 15, 15, 30));
+    }
+
+    #[test]
+    fn twelve_am_and_pm() {
+        assert_eq!(parse("x tomorrow 12am").expire_time(), at(10, 15, 0, 0));
+        assert_eq!(parse("x tomorrow 12pm").expire_time(), at(10, 15, 12, 0));
+    }
+
+    #[test]
+    fn all_day_only_when_time_missing() {
+        assert!(parse("pay rent on friday").is_all_day());
+        assert!(!parse("pay rent on friday 9am").is_all_day());
+        assert!(!parse("pay rent").is_all_day());
+        assert!(!parse("tea in 30 minutes").is_all_day());
+    }
+
+    #[test]
+    fn date_without_time_uses_default_time() {
+        let parsed = parse("pay rent today");
+        assert_eq!(parsed.date, Some(NaiveDate::from_ymd(2026, 10, 14)));
+        assert_eq!(parsed.time, None);
+        assert_eq!(parsed.expire_time(), at(10, 14, 9, 0));
+
+        // 默认时间来自设置
+        let parsed = parse_todo("pay rent today", now(), Weekday::Mon, NaiveTime::from_hms(8, 30, 0));
+        assert_eq!(parsed.expire_time(), at(10, 14, 8, 30));
+    }
+
+    #[test]
+    fn time_later_today() {
+        assert_eq!(parse("meeting 17:00").expire_time(), at(10, 14, 17, 0));
+    }
+
+    #[test]
+    fn passed_time_moves_to_tomorrow() {
+        assert_eq!(parse("meeting 8:00").expire_time(), at(10, 15, 8, 0));
+        assert_eq!(parse("sleep midnight").expire_time(), at(10, 15, 0, 0));
+    }
+
+    #[test]
+    fn tonight_and_noon() {
+        assert_eq!(parse("movie tonight").expire_time(), at(10, 14, 20, 0));
+        assert_eq!(parse("movie tonight 9pm").expire_time(), at(10, 14, 21, 0));
+        assert_eq!(parse("lunch noon").expire_time(), at(10, 14, 12, 0));
+    }
+
+    #[test]
+    fn day_after_tomorrow() {
+        let parsed = parse("dentist day after tomorrow 9:15");
+        assert_eq!(parsed.content, "dentist");
+        assert_eq!(parsed.expire_time(), at(10, 16, 9, 15));
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(parse("x friday").date, Some(NaiveDate::from_ymd(2026, 10, 16)));
+        assert_eq!(parse("x wednesday").date, Some(NaiveDate::from_ymd(2026, 10, 14)));
+        assert_eq!(parse("x next wednesday").date, Some(NaiveDate::from_ymd(2026, 10, 21)));
+        assert_eq!(parse("x on next mon").date, Some(NaiveDate::from_ymd(2026, 10, 19)));
+        assert_eq!(parse("x this sunday").date, Some(NaiveDate::from_ymd(2026, 10, 18)));
+    }
+
+    #[test]
+    fn weekday_abbreviation_needs_keyword() {
+        let parsed = parse("watch the sun");
+        assert_eq!(parsed.content, "watch the sun");
+        assert_eq!(parsed.date, None);
+    }
+
+    #[test]
+    fn relative_offsets() {
+        assert_eq!(parse("tea in 30 minutes").expire_time(), at(10, 14, 11, 0));
+        assert_eq!(parse("tea in an hour").expire_time(), at(10, 14, 11, 30));
+        assert_eq!(parse("review in 3 days").date, Some(NaiveDate::from_ymd(2026, 10, 17)));
+        assert_eq!(parse("review in two weeks").date, Some(NaiveDate::from_ymd(2026, 10, 28)));
+        assert_eq!(parse("in 3 apples").content, "in 3 apples");
+    }
+
+    #[test]
+    fn next_week_and_month() {
+        assert_eq!(parse("x next week").date, Some(NaiveDate::from_ymd(2026, 10, 21)));
+        assert_eq!(parse("x next month").date, Some(NaiveDate::from_ymd(2026, 11, 14)));
+        assert_eq!(parse_todo("x next month", NaiveDate::from_ymd(2026, 1, 31).and_hms(0, 0, 0), Weekday::Mon, nine()).date, Some(NaiveDate::from_ymd(2026, 2, 28)));
+        assert_eq!(parse_todo("x next month", NaiveDate::from_ymd(2026, 12, 5).and_hms(0, 0, 0), Weekday::Mon, nine()).date, Some(NaiveDate::from_ymd(2027, 1, 5)));
+    }
+
+    #[test]
+    fn absolute_dates() {
+        assert_eq!(parse("x 2026-12-25").date, Some(NaiveDate::from_ymd(2026, 12, 25)));
+        assert_eq!(parse("x 2027/01/02").date, Some(NaiveDate::from_ymd(2027, 1, 2)));
+        assert_eq!(parse("x oct 20th").date, Some(NaiveDate::from_ymd(2026, 10, 20)));
+        assert_eq!(parse("x 1 March").date, Some(NaiveDate::from_ymd(2027, 3, 1)));
+        assert_eq!(parse("I may go").content, "I may go");
+    }
+
+    #[test]
+    fn punctuation_is_ignored() {
+        let parsed = parse("call bob tomorrow, 3pm.");
+        assert_eq!(parsed.content, "call bob");
+        assert_eq!(parsed.expire_time(), at(10, 15, 15, 0));
+    }
+
+    #[test]
+    fn english_recurrence_is_kept_in_content() {
+        // 到期时间为下一次, 今天是周三
+        let parsed = parse("report every friday 17:00");
+        assert_eq!(parsed.content, "report every friday");
+        assert_eq!(parsed.expire_time(), at(10, 16, 17, 0));
+        assert_eq!(parse("x every wednesday 17:00").expire_time(), at(10, 14, 17, 0));
+        assert_eq!(parse("x every wed 8:00").expire_time(), at(10, 21, 8, 0)); // 今天这次已经过了
+
+        let parsed = parse("standup daily 10:00");
+        assert_eq!(parsed.content, "standup daily");
+        assert_eq!(parsed.expire_time(), at(10, 15, 10, 0));
+
+        assert_eq!(parse("x every wednesday").date, Some(NaiveDate::from_ymd(2026, 10, 14)));
+        assert!(parse("x every wednesday").is_all_day());
+        assert_eq!(parse("x every month").content, "x every month");
+    }
+
+    #[test]
+    fn chinese_relative_day_and_time() {
+        let parsed = parse("明天下午三点开会");
+        assert_eq!(parsed.content, "开会");
+        assert_eq!(parsed.expire_time(), at(10, 15, 15, 0));
+
+        assert_eq!(parse("后天上午十点半 体检").expire_time(), at(10, 16, 10, 30));
+        assert_eq!(parse("大后天 8点15分").expire_time(), at(10, 17, 8, 15));
+        assert_eq!(parse("今晚八点 看电影").expire_time(), at(10, 14, 20, 0));
+        assert_eq!(parse("明早 跑步").expire_time(), at(10, 15, 9, 0));
+        assert_eq!(parse("中午一点吃饭").expire_time(), at(10, 14, 13, 0));
+        // 晚上的小时数很小时是过了午夜
+        assert_eq!(parse("晚上两点三刻").expire_time(), at(10, 15, 2, 45));
+        assert_eq!(parse("明晚十二点").expire_time(), at(10, 16, 0, 0));
+        assert_eq!(parse("傍晚五点").expire_time(), at(10, 14, 17, 0));
+    }
+
+    #[test]
+    fn chinese_weekdays() {
+        let parsed = parse("下周一交报告");
+        assert_eq!(parsed.content, "交报告");
+        assert_eq!(parsed.date, Some(NaiveDate::from_ymd(2026, 10, 19)));
+
+        assert_eq!(parse("下星期三").date, Some(NaiveDate::from_ymd(2026, 10, 21)));
+        assert_eq!(parse("周五 交周报").date, Some(NaiveDate::from_ymd(2026, 10, 16)));
+        assert_eq!(parse("星期天").date, Some(NaiveDate::from_ymd(2026, 10, 18)));
+        assert_eq!(parse("本周一").date, Some(NaiveDate::from_ymd(2026, 10, 12)));
+        assert_eq!(parse("下周").date, Some(NaiveDate::from_ymd(2026, 10, 21)));
+        assert_eq!(parse("周末去爬山").content, "周末去爬山");
+    }
+
+    #[test]
+    fn week_starting_on_sunday() {
+        assert_eq!(parse("本周日").date, Some(NaiveDate::from_ymd(2026, 10, 18)));
+        assert_eq!(parse_todo("本周日", now(), Weekday::Sun, nine()).date, Some(NaiveDate::from_ymd(2026, 10, 11)));
+        assert_eq!(parse("下周日").date, Some(NaiveDate::from_ymd(2026, 10, 25)));
+        assert_eq!(parse_todo("下周日", now(), Weekday::Sun, nine()).date, Some(NaiveDate::from_ymd(2026, 10, 18)));
+        assert_eq!(parse_todo("下周一", now(), Weekday::Sun, nine()).date, Some(NaiveDate::from_ymd(2026, 10, 19)));
+    }
+
+    #[test]
+    fn chinese_dates_and_offsets() {
+        assert_eq!(parse("10月20日 生日").date, Some(NaiveDate::from_ymd(2026, 10, 20)));
+        assert_eq!(parse("三月一号").date, Some(NaiveDate::from_ymd(2027, 3, 1)));
+        assert_eq!(parse("20号还信用卡").date, Some(NaiveDate::from_ymd(2026, 10, 20)));
+        assert_eq!(parse("1号交房租").date, Some(NaiveDate::from_ymd(2026, 11, 1)));
+        assert_eq!(parse("3天后复查").date, Some(NaiveDate::from_ymd(2026, 10, 17)));
+        assert_eq!(parse("两个小时后 提醒").expire_time(), at(10, 14, 12, 30));
+        assert_eq!(parse("半小时后喝水").expire_time(), at(10, 14, 11, 0));
+        assert_eq!(parse("明天15:30开会").expire_time(), at(10, 15, 15, 30));
+    }
+
+    #[test]
+    fn chinese_recurrence_is_kept_in_content() {
+        let parsed = parse("每周五下午五点写周报");
+        assert_eq!(parsed.content, "每周五写周报");
+        assert_eq!(parsed.expire_time(), at(10, 16, 17, 0));
+
+        assert_eq!(parse("每天早上八点").content, "每天");
+        assert_eq!(parse("每天早上八点").expire_time(), at(10, 15, 8, 0));
+        assert_eq!(parse("每月1号交房租").content, "每月1号交房租");
+        assert_eq!(parse("每月1号交房租").date, Some(NaiveDate::from_ymd(2026, 11, 1)));
+        assert_eq!(parse("每月14号上午八点").expire_time(), at(11, 14, 8, 0));
+    }
+
+    #[test]
+    fn huge_numbers_are_plain_text() {
+        let parsed = parse("x in 999999999 days");
+        assert_eq!(parsed.content, "x in 999999999 days");
+        assert_eq!(parsed.date, None);
+        assert_eq!(parse("x in 4294967295 weeks").date, None);
+        assert_eq!(parse("x in 4294967295 hours").date, None);
+
+        assert_eq!(parse("999999999天后").date, None);
+        assert_eq!(parse("999999999天后").content, "999999999天后");
+        assert_eq!(parse("九九九九九九九九九九九").content, "九九九九九九九九九九九");
+        assert_eq!(parse("九九九九九九九九九九九").date, None);
+        assert_eq!(parse("二十九九九九九九九九九九号").date, None);
+    }
+
+    #[test]
+    fn chinese_numbers_in_content_are_kept() {
+        assert_eq!(parse("一起吃饭").content, "一起吃饭");
+        assert_eq!(parse("买三个苹果").content, "买三个苹果");
+    }
+
+    #[test]
+    fn mixed_languages() {
+        let parsed = parse("call bob 明天 3pm");
+        assert_eq!(parsed.content, "call bob");
+        assert_eq!(parsed.expire_time(), at(10, 15, 15, 0));
+    }
+
+    #[test]
+    fn month_end_is_clamped() {
+        let now = NaiveDate::from_ymd(2026, 2, 10).and_hms(12, 0, 0);
+        assert_eq!(parse_todo("31号", now, Weekday::Mon, nine()).date, Some(NaiveDate::from_ymd(2026, 2, 28)));
+    }
+
+    #[test]
+    fn result_is_valid_local_time() {
+        // 解析结果是本地时间, 除了 DST 切换的那一小时外都应该能转换到 Local
+        let now = Local::now().naive_local();
+        let parsed = parse_todo("x tomorrow 3pm", now, Weekday::Mon, nine());
+        let expire_time = parsed.expire_time().unwrap();
+        assert_eq!(expire_time.time(), NaiveTime::from_hms(15, 0, 0));
+        assert_eq!(expire_time.date(), now.date().succ());
+        assert!(Local.from_local_datetime(&expire_time).earliest().is_some());
+    }
+}
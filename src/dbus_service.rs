@@ -0,0 +1,297 @@
+use std::sync::Mutex;
+
+use chrono::NaiveDate;
+use gtk::{gio, glib};
+use gtk::glib::thread_guard::ThreadGuard;
+use gtk::prelude::*;
+
+use reminder_core::{local_date, StoreError, Todo, TodoRepository};
+
+use crate::reminder::add_todo_from_text;
+use crate::settings::Settings;
+
+// 供脚本和其他程序调用的 D-Bus 接口, 挂在主实例的 session bus 连接上
+pub const OBJECT_PATH: &str = "/org/rmb122/Reminder";
+pub const INTERFACE_NAME: &str = "org.rmb122.Reminder";
+
+const INTROSPECTION_XML: &str = r#"
+<node>
+  <interface name="org.rmb122.Reminder">
+    <method name="AddTodo">
+      <arg type="s" name="text" direction="in"/>
+      <arg type="s" name="date" direction="in"/>
+      <arg type="i" name="id" direction="out"/>
+    </method>
+    <method name="ListTodos">
+      <arg type="s" name="date" direction="in"/>
+      <arg type="a(isssb)" name="todos" direction="out"/>
+    </method>
+    <method name="CompleteTodo">
+      <arg type="i" name="id" direction="in"/>
+    </method>
+    <method name="DeleteTodo">
+      <arg type="i" name="id" direction="in"/>
+    </method>
+    <signal name="TodosChanged"/>
+  </interface>
+</node>
+"#;
+
+#[derive(Debug)]
+pub struct MethodError {
+    pub name: &'static str,
+    pub message: String,
+}
+
+fn invalid_args(message: String) -> MethodError {
+    MethodError { name: "org.rmb122.Reminder.Error.InvalidArgs", message: message }
+}
+
+fn not_found(todo_id: i32) -> MethodError {
+    MethodError { name: "org.rmb122.Reminder.Error.NotFound", message: format!("No todo with id {}", todo_id) }
+}
+
+impl From<StoreError> for MethodError {
+    fn from(e: StoreError) -> Self {
+        MethodError { name: "org.freedesktop.DBus.Error.Failed", message: format!("Database error: {}", e) }
+    }
+}
+
+// 空字符串表示没有日期
+fn parse_date(date: &str) -> Result<Option<NaiveDate>, MethodError> {
+    if date.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map(Some)
+        .map_err(|_| invalid_args(format!("Invalid date: {}, expected YYYY-MM-DD", date)))
+}
+
+fn format_due(todo: &Todo) -> String {
+    match todo.local_expire_time() {
+        Some(_) if todo.all_day => todo.local_date().unwrap().format("%Y-%m-%d").to_string(),
+        Some(time) => time.format("%Y-%m-%d %H:%M").to_string(),
+        None => String::new(),
+    }
+}
+
+fn get_args<T: glib::FromVariant>(parameters: &glib::Variant) -> Result<T, MethodError> {
+    parameters.get::<T>().ok_or_else(|| invalid_args(format!("Unexpected arguments {}", parameters.type_())))
+}
+
+fn is_read_only(method: &str) -> bool {
+    method == "ListTodos"
+}
+
+// 和界面使用同一个 store, 返回值为 D-Bus 方法的输出参数
+pub fn call_method(store: &dyn TodoRepository, settings: &Settings, method: &str, parameters: &glib::Variant) -> Result<Option<glib::Variant>, MethodError> {
+    match method {
+        "AddTodo" => {
+            let (text, date) = get_args::<(String, String)>(parameters)?;
+            if text.trim().is_empty() {
+                return Err(invalid_args(String::from("Todo text is empty")));
+            }
+            let todo_id = add_todo_from_text(store, settings, text.trim().to_string(), parse_date(&date)?, None)?;
+            Ok(Some((todo_id,).to_variant()))
+        }
+        "ListTodos" => {
+            let (date,) = get_args::<(String,)>(parameters)?;
+            let todos: Vec<(i32, String, String, String, bool)> = store.find_todo(parse_date(&date)?.map(local_date), None)?.iter()
+                .map(|todo| (todo.id, todo.content.clone(), todo.notes.clone(), format_due(todo), todo.completed))
+                .collect();
+            Ok(Some((todos,).to_variant()))
+        }
+        "CompleteTodo" | "DeleteTodo" => {
+            let (todo_id,) = get_args::<(i32,)>(parameters)?;
+            if store.get_todo(todo_id)?.is_none() {
+                return Err(not_found(todo_id));
+            }
+            match method {
+                "CompleteTodo" => store.set_completed(todo_id, true)?,
+                _ => store.del_todo(&[todo_id])?,
+            }
+            Ok(None)
+        }
+        _ => Err(MethodError { name: "org.freedesktop.DBus.Error.UnknownMethod", message: format!("Unknown method {}", method) }),
+    }
+}
+
+// 执行方法并把结果返回给调用方, 返回 true 表示 todo 被修改了
+pub fn handle_method_call(store: &dyn TodoRepository, settings: &Settings, method: &str, parameters: &glib::Variant, invocation: gio::DBusMethodInvocation) -> bool {
+    match call_method(store, settings, method, parameters) {
+        Ok(value) => {
+            invocation.return_value(value.as_ref());
+            return !is_read_only(method);
+        }
+        Err(e) => {
+            invocation.return_dbus_error(e.name, &e.message);
+            return false;
+        }
+    }
+}
+
+pub fn register<F>(connection: &gio::DBusConnection, method_call: F) -> Result<gio::RegistrationId, glib::Error>
+    where F: Fn(&str, &glib::Variant, gio::DBusMethodInvocation) + 'static {
+    register_object(connection, OBJECT_PATH, INTROSPECTION_XML, INTERFACE_NAME, method_call, |_| None)
+}
+
+// 在主线程中注册 D-Bus 对象, get_property 返回 None 表示没有这个属性
+pub fn register_object<F, P>(connection: &gio::DBusConnection, object_path: &str, xml: &str, interface_name: &str,
+                             method_call: F, get_property: P) -> Result<gio::RegistrationId, glib::Error>
+    where F: Fn(&str, &glib::Variant, gio::DBusMethodInvocation) + 'static,
+          P: Fn(&str) -> Option<glib::Variant> + 'static {
+    let interface_info = gio::DBusNodeInfo::for_xml(xml)?.lookup_interface(interface_name).unwrap();
+    // 回调总是在注册时的主线程中调用, ThreadGuard 只是为了满足 Send + Sync 的要求
+    let method_call = Mutex::new(ThreadGuard::new(method_call));
+    let get_property = Mutex::new(ThreadGuard::new(get_property));
+    connection.register_object(
+        object_path,
+        &interface_info,
+        move |_, _, _, _, method, parameters, invocation| {
+            (method_call.lock().unwrap().get_ref())(method, &parameters, invocation);
+        },
+        move |_, _, _, _, property| {
+            (get_property.lock().unwrap().get_ref())(property).unwrap_or_else(|| ().to_variant())
+        },
+        |_, _, _, _, _, _| { false }, // 属性都是只读的
+    )
+}
+
+pub fn emit_todos_changed(connection: &gio::DBusConnection) {
+    if let Err(e) = connection.emit_signal(None, OBJECT_PATH, INTERFACE_NAME, "TodosChanged", None) {
+        eprintln!("Error emitting TodosChanged: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::ffi::CStr;
+    use std::rc::Rc;
+
+    use gtk::{gio, glib};
+    use gtk::prelude::*;
+
+    use reminder_core::{MemoryTodoStore, TodoRepository};
+
+    use crate::settings::Settings;
+
+    use super::{call_method, emit_todos_changed, handle_method_call, register, INTERFACE_NAME, OBJECT_PATH};
+
+    type TodoItem = (i32, String, String, String, bool);
+
+    // gio 0.15 没有 GTestDBus 的绑定, 直接调用 C 接口启动一个临时的 session bus
+    struct TestBus(*mut gio::ffi::GTestDBus);
+
+    impl TestBus {
+        fn up() -> TestBus {
+            unsafe {
+                let bus = gio::ffi::g_test_dbus_new(gio::ffi::G_TEST_DBUS_NONE);
+                gio::ffi::g_test_dbus_up(bus);
+                return TestBus(bus);
+            }
+        }
+
+        fn connect(&self) -> gio::DBusConnection {
+            let address = unsafe { CStr::from_ptr(gio::ffi::g_test_dbus_get_bus_address(self.0)) }.to_str().unwrap().to_string();
+            let flags = gio::DBusConnectionFlags::AUTHENTICATION_CLIENT | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION;
+            gio::DBusConnection::for_address_sync(&address, flags, None, None::<&gio::Cancellable>).unwrap()
+        }
+    }
+
+    impl Drop for TestBus {
+        fn drop(&mut self) {
+            unsafe {
+                gio::ffi::g_test_dbus_down(self.0);
+                glib::gobject_ffi::g_object_unref(self.0 as *mut _);
+            }
+        }
+    }
+
+    #[test]
+    fn add_list_and_delete() {
+        let store = MemoryTodoStore::new();
+        let settings = Settings::default();
+        let todo_id = call_method(&store, &settings, "AddTodo", &("buy milk", "2026-10-20").to_variant()).unwrap().unwrap()
+            .get::<(i32,)>().unwrap().0;
+        call_method(&store, &settings, "AddTodo", &("no date", "").to_variant()).unwrap();
+
+        let list = |date: &str| call_method(&store, &settings, "ListTodos", &(date,).to_variant()).unwrap().unwrap()
+            .get::<(Vec<TodoItem>,)>().unwrap().0;
+        assert_eq!(list("2026-10-20"), vec![(todo_id, String::from("buy milk"), String::new(), String::from("2026-10-20"), false)]);
+        let todos = list("");
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].1, "no date");
+
+        // 完成后仍然保留在列表中
+        assert!(call_method(&store, &settings, "CompleteTodo", &(todo_id,).to_variant()).unwrap().is_none());
+        assert!(list("2026-10-20")[0].4);
+
+        assert!(call_method(&store, &settings, "DeleteTodo", &(todo_id,).to_variant()).unwrap().is_none());
+        assert!(list("2026-10-20").is_empty());
+        assert_eq!(call_method(&store, &settings, "CompleteTodo", &(todo_id,).to_variant()).unwrap_err().name, "org.rmb122.Reminder.Error.NotFound");
+    }
+
+    #[test]
+    fn methods_and_signal_over_bus() {
+        let bus = TestBus::up();
+        let context = glib::MainContext::new();
+        context.with_thread_default(|| {
+            let server = bus.connect();
+            let client = bus.connect();
+
+            // 和 Reminder::register_dbus_service 一样, 修改后发出 TodosChanged
+            let store = Rc::new(MemoryTodoStore::new());
+            let store_clone = store.clone();
+            let server_clone = server.clone();
+            register(&server, move |method, parameters, invocation| {
+                if handle_method_call(store_clone.as_ref(), &Settings::default(), method, parameters, invocation) {
+                    emit_todos_changed(&server_clone);
+                }
+            }).unwrap();
+
+            let changed = Rc::new(Cell::new(0));
+            let changed_clone = changed.clone();
+            client.signal_subscribe(None, Some(INTERFACE_NAME), Some("TodosChanged"), Some(OBJECT_PATH), None, gio::DBusSignalFlags::NONE,
+                                    move |_, _, _, _, _, _| changed_clone.set(changed_clone.get() + 1));
+
+            let server_name = server.unique_name().unwrap().to_string();
+            let call = |method: &str, parameters: glib::Variant| {
+                context.block_on(client.call_future(Some(&server_name), OBJECT_PATH, INTERFACE_NAME, method, Some(&parameters),
+                                                    None, gio::DBusCallFlags::NONE, 5000))
+            };
+
+            let todo_id = call("AddTodo", ("buy milk", "2026-10-20").to_variant()).unwrap().get::<(i32,)>().unwrap().0;
+            let todos = call("ListTodos", ("2026-10-20",).to_variant()).unwrap().get::<(Vec<TodoItem>,)>().unwrap().0;
+            assert_eq!(todos, vec![(todo_id, String::from("buy milk"), String::new(), String::from("2026-10-20"), false)]);
+            // 信号在 AddTodo 的返回值之后发出, 收到 ListTodos 的返回值时已经处理过了
+            assert_eq!(changed.get(), 1);
+
+            call("CompleteTodo", (todo_id,).to_variant()).unwrap();
+            assert!(store.get_todo(todo_id).unwrap().unwrap().completed);
+            let error = call("CompleteTodo", (todo_id + 1,).to_variant()).unwrap_err();
+            assert!(error.message().starts_with("GDBus.Error:org.rmb122.Reminder.Error.NotFound:"));
+            assert_eq!(changed.get(), 2);
+        }).unwrap();
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        let store = MemoryTodoStore::new();
+        let settings = Settings::default();
+        assert_eq!(call_method(&store, &settings, "AddTodo", &("x", "tomorrow").to_variant()).unwrap_err().name, "org.rmb122.Reminder.Error.InvalidArgs");
+        assert_eq!(call_method(&store, &settings, "AddTodo", &(" ", "").to_variant()).unwrap_err().name, "org.rmb122.Reminder.Error.InvalidArgs");
+        assert_eq!(call_method(&store, &settings, "DeleteTodo", &("1",).to_variant()).unwrap_err().name, "org.rmb122.Reminder.Error.InvalidArgs");
+    }
+
+    #[test]
+    fn default_alarm_for_dated_todo() {
+        let store = MemoryTodoStore::new();
+        let settings = Settings { default_alarm_minutes: Some(15), ..Settings::default() };
+        let dated_id = call_method(&store, &settings, "AddTodo", &("buy milk", "2026-10-20").to_variant()).unwrap().unwrap()
+            .get::<(i32,)>().unwrap().0;
+        let undated_id = call_method(&store, &settings, "AddTodo", &("no date", "").to_variant()).unwrap().unwrap()
+            .get::<(i32,)>().unwrap().0;
+        assert_eq!(store.find_alarm(dated_id).unwrap().iter().map(|alarm| alarm.trigger_offset).collect::<Vec<_>>(), vec![Some(15)]);
+        assert!(store.find_alarm(undated_id).unwrap().is_empty());
+    }
+}
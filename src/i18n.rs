@@ -0,0 +1,51 @@
+use gettextrs::{bind_textdomain_codeset, bindtextdomain, LocaleCategory, setlocale, textdomain};
+
+const GETTEXT_PACKAGE: &str = "rust-reminder";
+
+// 编译时可以用 LOCALEDIR 环境变量指定 .mo 文件的安装位置, 例如 LOCALEDIR=$HOME/.local/share/locale
+const LOCALEDIR: Option<&str> = option_env!("LOCALEDIR");
+const DEFAULT_LOCALEDIR: &str = "/usr/share/locale";
+
+// 只标记需要翻译的字符串, 方便 xgettext 提取, 用到的时候再调用 gettext
+macro_rules! N_ {
+    ($msgid:expr) => { $msgid };
+}
+
+// 需要在创建任何界面和注册命令行参数之前调用
+pub fn init_i18n() {
+    setlocale(LocaleCategory::LcAll, "");
+    let locale_dir = LOCALEDIR.unwrap_or(DEFAULT_LOCALEDIR);
+    if let Err(e) = bindtextdomain(GETTEXT_PACKAGE, locale_dir) {
+        eprintln!("Error binding text domain to {}: {}", locale_dir, e);
+    }
+    if let Err(e) = bind_textdomain_codeset(GETTEXT_PACKAGE, "UTF-8") {
+        eprintln!("Error setting text domain codeset: {}", e);
+    }
+    if let Err(e) = textdomain(GETTEXT_PACKAGE) {
+        eprintln!("Error setting text domain: {}", e);
+    }
+}
+
+// 翻译后的字符串不能用 format!, 按顺序把其中的 {} 替换为 args, 替换进去的内容不会再被替换
+pub fn fill_placeholders(template: &str, args: &[&str]) -> String {
+    let mut parts = template.split("{}");
+    let mut text = String::from(parts.next().unwrap_or(""));
+    for (i, part) in parts.enumerate() {
+        text.push_str(args.get(i).copied().unwrap_or("{}"));
+        text.push_str(part);
+    }
+    return text;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fill_placeholders;
+
+    #[test]
+    fn placeholders_are_filled_in_order() {
+        assert_eq!(fill_placeholders("Error reading {}: {}", &["a.ics", "denied"]), "Error reading a.ics: denied");
+        assert_eq!(fill_placeholders("读取 {} 时出错: {}", &["{}", "x"]), "读取 {} 时出错: x");
+        assert_eq!(fill_placeholders("{} todos", &[]), "{} todos");
+        assert_eq!(fill_placeholders("no placeholder", &["x"]), "no placeholder");
+    }
+}
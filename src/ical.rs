@@ -0,0 +1,504 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use reminder_core::{Alarm, Attachment, NewAlarm, NewAttachment, NewTodo, parse_timezone, PRIORITY_HIGH, PRIORITY_LOW, PRIORITY_MEDIUM, PRIORITY_NONE, Todo, wall_to_utc};
+
+const ICAL_DATETIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+const ICAL_DATE_FORMAT: &str = "%Y%m%d";
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace("\r\n", "\\n").replace('\n', "\\n")
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(c) => result.push(c),
+            None => {}
+        }
+    }
+    return result;
+}
+
+// RFC 5545 要求每行不超过 75 字节, 超出的部分以空格开头续行
+fn fold_line(line: &str) -> String {
+    let mut result = String::new();
+    let mut line_len = 0;
+    for c in line.chars() {
+        if line_len + c.len_utf8() > 75 {
+            result.push_str("\r\n ");
+            line_len = 1;
+        }
+        result.push(c);
+        line_len += c.len_utf8();
+    }
+    result.push_str("\r\n");
+    return result;
+}
+
+// 返回 (UTC 时间, 是否全天), 没有 Z 后缀也没有 TZID 的 floating time 按本地时间处理
+fn parse_time(value: &str, timezone: Option<&str>) -> Option<(NaiveDateTime, bool)> {
+    if let Some(value) = value.strip_suffix('Z') {
+        return NaiveDateTime::parse_from_str(value, ICAL_DATETIME_FORMAT).ok().map(|time| (time, false));
+    }
+
+    match NaiveDateTime::parse_from_str(value, ICAL_DATETIME_FORMAT) {
+        Ok(time) => Some((wall_to_utc(&time, timezone), false)),
+        Err(_) => NaiveDate::parse_from_str(value, ICAL_DATE_FORMAT).ok().map(|date| (date.and_hms(0, 0, 0), true)),
+    }
+}
+
+// time 为数据库中存的时间, wall_time 为 todo 时区中的时间
+fn format_time_property(name: &str, todo: &Todo, time: Option<NaiveDateTime>, wall_time: Option<NaiveDateTime>) -> Option<String> {
+    match (time, wall_time, &todo.timezone) {
+        (_, Some(wall_time), _) if todo.all_day => Some(format!("{};VALUE=DATE:{}", name, wall_time.format(ICAL_DATE_FORMAT))),
+        (_, Some(wall_time), Some(timezone)) => Some(format!("{};TZID={}:{}", name, timezone, wall_time.format(ICAL_DATETIME_FORMAT))),
+        (Some(time), _, None) => Some(format!("{}:{}Z", name, time.format(ICAL_DATETIME_FORMAT))),
+        _ => None,
+    }
+}
+
+// 提醒时间相对到期时间的偏移, 例如 -PT15M, -P1D
+fn format_duration(minutes: i64) -> String {
+    let sign = if minutes < 0 { "-" } else { "" };
+    let minutes = minutes.abs();
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+
+    let mut result = format!("{}P", sign);
+    if days > 0 {
+        result.push_str(&format!("{}D", days));
+    }
+    if hours > 0 || minutes > 0 || days == 0 {
+        result.push('T');
+        if hours > 0 {
+            result.push_str(&format!("{}H", hours));
+        }
+        if minutes > 0 || hours == 0 {
+            result.push_str(&format!("{}M", minutes));
+        }
+    }
+    return result;
+}
+
+// 返回分钟数, 秒会被舍去. 溢出时返回 None
+fn parse_duration(value: &str) -> Option<i64> {
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => (-1, value),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value.strip_prefix('P')?;
+
+    let mut seconds = 0i64;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => in_time = true,
+            _ => {
+                let n = number.parse::<i64>().ok()?;
+                number.clear();
+                let unit = match (c, in_time) {
+                    ('W', false) => 7 * 24 * 3600,
+                    ('D', false) => 24 * 3600,
+                    ('H', true) => 3600,
+                    ('M', true) => 60,
+                    ('S', true) => 1,
+                    _ => return None,
+                };
+                seconds = seconds.checked_add(n.checked_mul(unit)?)?;
+            }
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    return Some(sign * seconds / 60);
+}
+
+// iCalendar 中 1 最高, 9 最低, 0 表示没有优先级
+fn format_priority(priority: i32) -> Option<i32> {
+    match priority {
+        PRIORITY_HIGH => Some(1),
+        PRIORITY_MEDIUM => Some(5),
+        PRIORITY_LOW => Some(9),
+        _ => None,
+    }
+}
+
+// 1-4 为高, 5 为中, 6-9 为低, 和 RFC 5545 中的 CUA 约定一致
+fn parse_priority(value: &str) -> i32 {
+    match value.trim().parse::<i32>() {
+        Ok(1..=4) => PRIORITY_HIGH,
+        Ok(5) => PRIORITY_MEDIUM,
+        Ok(6..=9) => PRIORITY_LOW,
+        _ => PRIORITY_NONE,
+    }
+}
+
+fn format_alarm(todo: &Todo, alarm: &Alarm) -> Vec<String> {
+    let trigger = match (alarm.trigger_time, alarm.trigger_offset) {
+        (Some(time), _) => format!("TRIGGER;VALUE=DATE-TIME:{}Z", time.format(ICAL_DATETIME_FORMAT)),
+        (None, Some(offset)) => format!("TRIGGER;RELATED=END:{}", format_duration(-(offset as i64))), // VTODO 的 END 即 DUE
+        (None, None) => return Vec::new(),
+    };
+    vec![
+        String::from("BEGIN:VALARM"),
+        String::from("ACTION:DISPLAY"),
+        format!("DESCRIPTION:{}", escape_text(&todo.content)),
+        trigger,
+        String::from("END:VALARM"),
+    ]
+}
+
+pub struct ImportedTodo {
+    pub todo: NewTodo,
+    pub alarms: Vec<NewAlarm>,
+    pub attachments: Vec<NewAttachment>,
+    pub completed: bool, // NewTodo 中没有完成状态, 导入后另外设置
+}
+
+pub fn export_todos(todos: &[Todo], alarms: &[Alarm], attachments: &[Attachment]) -> String {
+    let dtstamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//rmb122//rust-reminder//EN"),
+    ];
+
+    for todo in todos.iter() {
+        lines.push(String::from("BEGIN:VTODO"));
+        lines.push(format!("UID:{}@rust-reminder", todo.id));
+        lines.push(format!("DTSTAMP:{}", dtstamp));
+        lines.push(format!("SUMMARY:{}", escape_text(&todo.content)));
+        if !todo.notes.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape_text(&todo.notes)));
+        }
+        lines.push(String::from(if todo.completed { "STATUS:COMPLETED" } else { "STATUS:NEEDS-ACTION" }));
+        if let Some(priority) = format_priority(todo.priority) {
+            lines.push(format!("PRIORITY:{}", priority));
+        }
+        if let Some(line) = format_time_property("DTSTART", todo, todo.start_time, todo.wall_start_time()) {
+            lines.push(line);
+        }
+        if let Some(line) = format_time_property("DUE", todo, todo.expire_time, todo.wall_expire_time()) {
+            lines.push(line);
+        }
+        for attachment in attachments.iter().filter(|attachment| attachment.todo_id == todo.id) {
+            lines.push(format!("ATTACH:{}", attachment.uri));
+        }
+        for alarm in alarms.iter().filter(|alarm| alarm.todo_id == todo.id) {
+            lines.extend(format_alarm(todo, alarm));
+        }
+        lines.push(String::from("END:VTODO"));
+    }
+
+    lines.push(String::from("END:VCALENDAR"));
+    return lines.iter().map(|line| fold_line(line)).collect();
+}
+
+pub fn import_todos(content: &str) -> Vec<ImportedTodo> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        match lines.last_mut() {
+            Some(last) if line.starts_with(' ') || line.starts_with('\t') => {
+                last.push_str(&line[1..]);
+            }
+            _ => {
+                lines.push(String::from(line));
+            }
+        }
+    }
+
+    let mut todos = Vec::new();
+    let mut current_todo: Option<NewTodo> = None;
+    let mut current_start: Option<(NaiveDateTime, bool)> = None;
+    let mut current_alarms: Vec<NewAlarm> = Vec::new();
+    let mut current_attachments: Vec<NewAttachment> = Vec::new();
+    let mut current_completed = false;
+    let mut in_alarm = false;
+    for line in lines.iter() {
+        let (name, value) = match line.split_once(':') {
+            Some(property) => property,
+            None => continue,
+        };
+        let mut params = name.split(';');
+        let name = params.next().unwrap().to_uppercase();
+        let params: Vec<(&str, &str)> = params.filter_map(|param| param.split_once('=')).collect();
+        // 只认 TZID 参数, 而且只支持 IANA 时区名, 不解析 VTIMEZONE
+        let timezone = params.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("TZID"))
+            .and_then(|(_, value)| parse_timezone(value.trim_matches('"')))
+            .map(|tz| String::from(tz.name()));
+
+        match name.as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VALARM") && current_todo.is_some() => {
+                in_alarm = true;
+            }
+            "END" if value.eq_ignore_ascii_case("VALARM") => {
+                in_alarm = false;
+            }
+            // 相对提醒一律按相对到期时间处理
+            "TRIGGER" if in_alarm => {
+                let absolute = params.iter().any(|(key, value)| key.eq_ignore_ascii_case("VALUE") && value.eq_ignore_ascii_case("DATE-TIME"));
+                let alarm = match absolute {
+                    true => parse_time(value, timezone.as_deref()).map(|(time, all_day)| NewAlarm {
+                        trigger_time: Some(if all_day { wall_to_utc(&time, None) } else { time }),
+                        trigger_offset: None,
+                    }),
+                    // 超出 i32 的偏移没有意义, 跳过这个提醒
+                    false => parse_duration(value).and_then(|minutes| i32::try_from(-minutes).ok()).map(|offset| NewAlarm {
+                        trigger_time: None,
+                        trigger_offset: Some(offset),
+                    }),
+                };
+                current_alarms.extend(alarm);
+            }
+            _ if in_alarm => {}
+            "BEGIN" if value.eq_ignore_ascii_case("VTODO") => {
+                current_todo = Some(NewTodo {
+                    content: String::new(),
+                    expire_time: None,
+                    timezone: None,
+                    all_day: false,
+                    start_time: None,
+                    hide_until_start: false,
+                    notes: String::new(),
+                    list_id: None,
+                    priority: PRIORITY_NONE,
+                });
+                current_start = None;
+                current_alarms.clear();
+                current_attachments.clear();
+                current_completed = false;
+                in_alarm = false;
+            }
+            "END" if value.eq_ignore_ascii_case("VTODO") => {
+                if let Some(mut todo) = current_todo.take() {
+                    // DTSTART 和 DUE 一个是日期一个是时间时, 按 DUE 的方式存储
+                    todo.start_time = match current_start.take() {
+                        Some((start, true)) if !todo.all_day => Some(wall_to_utc(&start, None)),
+                        Some((start, false)) if todo.all_day => Some(Local.from_utc_datetime(&start).date().naive_local().and_hms(0, 0, 0)),
+                        Some((start, _)) => Some(start),
+                        None => None,
+                    };
+                    todos.push(ImportedTodo {
+                        todo: todo,
+                        alarms: current_alarms.drain(..).collect(),
+                        attachments: current_attachments.drain(..).collect(),
+                        completed: current_completed,
+                    });
+                }
+            }
+            "SUMMARY" => {
+                if let Some(todo) = current_todo.as_mut() {
+                    todo.content = unescape_text(value);
+                }
+            }
+            // 内嵌的二进制附件不导入
+            "ATTACH" if current_todo.is_some() && !params.iter().any(|(key, _)| key.eq_ignore_ascii_case("ENCODING")) => {
+                current_attachments.push(NewAttachment { uri: String::from(value) });
+            }
+            "DESCRIPTION" => {
+                if let Some(todo) = current_todo.as_mut() {
+                    todo.notes = unescape_text(value);
+                }
+            }
+            // 有的程序只写 COMPLETED 时间, 不写 STATUS
+            "STATUS" if current_todo.is_some() => {
+                current_completed = value.trim().eq_ignore_ascii_case("COMPLETED");
+            }
+            "COMPLETED" if current_todo.is_some() => {
+                current_completed = true;
+            }
+            "PRIORITY" => {
+                if let Some(todo) = current_todo.as_mut() {
+                    todo.priority = parse_priority(value);
+                }
+            }
+            "DUE" => {
+                if let Some(todo) = current_todo.as_mut() {
+                    if let Some((time, all_day)) = parse_time(value, timezone.as_deref()) {
+                        todo.expire_time = Some(time);
+                        todo.timezone = if all_day { None } else { timezone };
+                        todo.all_day = all_day;
+                    }
+                }
+            }
+            "DTSTART" if current_todo.is_some() => {
+                current_start = parse_time(value, timezone.as_deref());
+            }
+            _ => {}
+        }
+    }
+    return todos;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use reminder_core::{Alarm, Attachment, NewAlarm, NewAttachment, PRIORITY_HIGH, PRIORITY_LOW, PRIORITY_MEDIUM, PRIORITY_NONE, Todo};
+
+    use super::{export_todos, import_todos, parse_duration};
+
+    fn get_todo(id: i32, content: &str) -> Todo {
+        Todo {
+            id: id,
+            content: String::from(content),
+            expire_time: None,
+            position: 0,
+            timezone: None,
+            all_day: false,
+            start_time: None,
+            hide_until_start: false,
+            notes: String::new(),
+            list_id: None,
+            completed: false,
+            priority: PRIORITY_NONE,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let due = NaiveDate::from_ymd(2026, 10, 20).and_hms(1, 0, 0);
+        let mut todo = get_todo(1, "buy milk, eggs; bread \\ butter");
+        todo.notes = String::from("第一行\n第二行 很长很长很长很长很长很长很长很长很长很长很长很长很长很长很长很长很长很长");
+        todo.expire_time = Some(due);
+        todo.timezone = Some(String::from("Asia/Tokyo"));
+        todo.start_time = Some(NaiveDate::from_ymd(2026, 10, 19).and_hms(1, 0, 0));
+        todo.priority = PRIORITY_HIGH;
+
+        let mut all_day = get_todo(2, "holiday");
+        all_day.expire_time = Some(NaiveDate::from_ymd(2026, 10, 22).and_hms(0, 0, 0));
+        all_day.all_day = true;
+        all_day.priority = PRIORITY_MEDIUM;
+
+        let mut undated = get_todo(3, "someday");
+        undated.completed = true;
+
+        let alarms = vec![
+            Alarm { id: 1, todo_id: 1, trigger_time: None, trigger_offset: Some(90) },
+            Alarm { id: 2, todo_id: 1, trigger_time: Some(NaiveDate::from_ymd(2026, 10, 19).and_hms(23, 30, 0)), trigger_offset: None },
+        ];
+        let attachments = vec![Attachment { id: 1, todo_id: 1, uri: String::from("file:///tmp/list.txt") }];
+
+        let content = export_todos(&[todo.clone(), all_day.clone(), undated], &alarms, &attachments);
+        assert!(content.lines().all(|line| line.len() <= 75));
+
+        let imported = import_todos(&content);
+        assert_eq!(imported.len(), 3);
+
+        let first = &imported[0];
+        assert_eq!(first.todo.content, todo.content);
+        assert_eq!(first.todo.notes, todo.notes);
+        assert_eq!(first.todo.expire_time, todo.expire_time);
+        assert_eq!(first.todo.timezone.as_deref(), Some("Asia/Tokyo"));
+        assert_eq!(first.todo.start_time, todo.start_time);
+        assert!(!first.todo.all_day);
+        assert!(!first.completed);
+        assert_eq!(first.todo.priority, PRIORITY_HIGH);
+        assert_eq!(first.alarms, vec![
+            NewAlarm { trigger_time: None, trigger_offset: Some(90) },
+            NewAlarm { trigger_time: alarms[1].trigger_time, trigger_offset: None },
+        ]);
+        assert_eq!(first.attachments, vec![NewAttachment { uri: String::from("file:///tmp/list.txt") }]);
+
+        let second = &imported[1];
+        assert_eq!(second.todo.expire_time, all_day.expire_time);
+        assert!(second.todo.all_day);
+        assert_eq!(second.todo.timezone, None);
+        assert_eq!(second.todo.priority, PRIORITY_MEDIUM);
+
+        let third = &imported[2];
+        assert_eq!(third.todo.content, "someday");
+        assert_eq!(third.todo.expire_time, None);
+        assert!(third.alarms.is_empty());
+        assert_eq!(third.todo.priority, PRIORITY_NONE);
+        assert!(third.completed);
+    }
+
+    #[test]
+    fn priority_ranges() {
+        let content = |priority: &str| format!("BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nSUMMARY:x\r\nPRIORITY:{}\r\nEND:VTODO\r\nEND:VCALENDAR\r\n", priority);
+        let priority = |value: &str| import_todos(&content(value))[0].todo.priority;
+        assert_eq!(priority("2"), PRIORITY_HIGH);
+        assert_eq!(priority("5"), PRIORITY_MEDIUM);
+        assert_eq!(priority("7"), PRIORITY_LOW);
+        assert_eq!(priority("0"), PRIORITY_NONE);
+        assert_eq!(priority("high"), PRIORITY_NONE);
+    }
+
+    #[test]
+    fn completed_status() {
+        let content = |property: &str| format!("BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nSUMMARY:x\r\n{}\r\nEND:VTODO\r\nEND:VCALENDAR\r\n", property);
+        let completed = |property: &str| import_todos(&content(property))[0].completed;
+        assert!(completed("STATUS:COMPLETED"));
+        assert!(completed("COMPLETED:20261020T090000Z"));
+        assert!(!completed("STATUS:NEEDS-ACTION"));
+        assert!(!completed("STATUS:IN-PROCESS"));
+    }
+
+    #[test]
+    fn overflowing_durations_are_skipped() {
+        assert_eq!(parse_duration("-P999999999999999D"), None);
+        assert_eq!(parse_duration("P100000000000000DT999999999999999H"), None);
+        assert_eq!(parse_duration("-P1DT2H30M"), Some(-(24 * 60 + 150)));
+
+        let content = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VTODO\r\n\
+            SUMMARY:far away\r\n\
+            DUE:20261020T090000Z\r\n\
+            BEGIN:VALARM\r\n\
+            TRIGGER:-P99999999999999D\r\n\
+            END:VALARM\r\n\
+            BEGIN:VALARM\r\n\
+            TRIGGER:-P9999999D\r\n\
+            END:VALARM\r\n\
+            BEGIN:VALARM\r\n\
+            TRIGGER:-PT15M\r\n\
+            END:VALARM\r\n\
+            END:VTODO\r\n\
+            END:VCALENDAR\r\n";
+        let imported = import_todos(content);
+        assert_eq!(imported[0].alarms, vec![NewAlarm { trigger_time: None, trigger_offset: Some(15) }]);
+    }
+
+    #[test]
+    fn malformed_components_are_skipped() {
+        // VEVENT 不导入, 没有 END 也不影响后面的 VTODO; 无法解析的属性被忽略
+        let content = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:meeting\r\n\
+            DTSTART:not a date\r\n\
+            BEGIN:VALARM\r\n\
+            TRIGGER:-PT15M\r\n\
+            END:VALARM\r\n\
+            this line has no colon\r\n\
+            BEGIN:VTODO\r\n\
+            SUMMARY:call bob\r\n\
+            DUE:2026-13-45\r\n\
+            DTSTART;TZID=Not/AZone:20261020T090000\r\n\
+            BEGIN:VALARM\r\n\
+            TRIGGER:-PXM\r\n\
+            END:VALARM\r\n\
+            END:VTODO\r\n\
+            BEGIN:VTODO\r\n\
+            SUMMARY:no end\r\n\
+            END:VCALENDAR\r\n";
+
+        let imported = import_todos(content);
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].todo.content, "call bob");
+        assert_eq!(imported[0].todo.expire_time, None);
+        assert_eq!(imported[0].todo.timezone, None);
+        assert!(imported[0].alarms.is_empty());
+    }
+}
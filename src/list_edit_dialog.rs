@@ -0,0 +1,45 @@
+use gettextrs::gettext;
+use gtk::gdk;
+use gtk::prelude::*;
+
+use reminder_core::{NewTodoList, TodoList};
+
+const DEFAULT_LIST_COLOR: &str = "#3584e4";
+
+fn format_color(rgba: &gdk::RGBA) -> String {
+    let channel = |c: f64| (c * 255f64).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(rgba.red()), channel(rgba.green()), channel(rgba.blue()))
+}
+
+// 新建或编辑清单, list 为 None 时新建, 取消或者名字为空时返回 None
+pub fn run_list_edit_dialog(parent: Option<&gtk::Window>, title: &str, list: Option<&TodoList>) -> Option<NewTodoList> {
+    let dialog = gtk::Dialog::with_buttons(Some(title), parent, gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+                                           &[(&gettext("Cancel"), gtk::ResponseType::Cancel), (&gettext("Save"), gtk::ResponseType::Accept)]);
+    dialog.set_default_response(gtk::ResponseType::Accept);
+
+    let name_entry = gtk::Entry::builder().text(list.map_or("", |l| l.name.as_str())).activates_default(true).hexpand(true).build();
+    let color = gdk::RGBA::parse(list.map_or(DEFAULT_LIST_COLOR, |l| l.color.as_str()))
+        .unwrap_or_else(|_| gdk::RGBA::parse(DEFAULT_LIST_COLOR).unwrap());
+    let color_button = gtk::ColorButton::with_rgba(&color);
+
+    let grid = gtk::Grid::builder().row_spacing(6).column_spacing(6).border_width(6).build();
+    grid.attach(&gtk::Label::builder().label(&gettext("Name")).halign(gtk::Align::End).build(), 0, 0, 1, 1);
+    grid.attach(&name_entry, 1, 0, 1, 1);
+    grid.attach(&gtk::Label::builder().label(&gettext("Color")).halign(gtk::Align::End).build(), 0, 1, 1, 1);
+    grid.attach(&color_button, 1, 1, 1, 1);
+    dialog.content_area().pack_start(&grid, true, true, 0);
+    dialog.show_all();
+
+    let response = dialog.run();
+    let name = name_entry.text().trim().to_string();
+    let color = format_color(&color_button.rgba());
+    dialog.close();
+
+    if response != gtk::ResponseType::Accept || name.is_empty() {
+        return None;
+    }
+    Some(NewTodoList {
+        name: name,
+        color: color,
+    })
+}
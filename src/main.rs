@@ -1,22 +1,133 @@
-#[macro_use]
-extern crate diesel;
-#[macro_use]
-extern crate diesel_migrations;
-
-
+use chrono::NaiveDate;
+use gettextrs::gettext;
+use gtk::{gio, glib};
 use gtk::prelude::*;
 
+use reminder_core::{local_date, StoreResult, TodoRepository, TodoStore};
+
+use crate::i18n::{fill_placeholders, init_i18n};
 use crate::reminder::Reminder;
+use crate::service_files::install_service_files;
+use crate::settings::Settings;
+use crate::utils::{get_todo_time_text, show_error_dialog};
 
+#[macro_use]
+mod i18n;
+mod alarm_scheduler;
+mod date_parser;
+mod dbus_service;
+mod markdown;
 mod utils;
 mod reminder;
-mod models;
-mod schema;
+mod ical;
+mod list_edit_dialog;
+mod preferences_dialog;
 mod reminder_edit_dialog;
+mod service_files;
+mod settings;
+mod shortcuts;
+mod theme;
+mod tray_icon;
 
 fn main() {
+    init_i18n();
+
+    // 同一时间只有一个实例, 再次启动时命令行通过 D-Bus 转发给已经运行的实例
     let application =
-        gtk::Application::new(Some("com.rmb122.reminder"), Default::default());
-    application.connect_activate(|app| { Reminder::new().build_ui(app) });
+        gtk::Application::new(Some("com.rmb122.reminder"), gio::ApplicationFlags::HANDLES_COMMAND_LINE);
+    application.add_main_option("add", glib::Char::from(b'a'), glib::OptionFlags::NONE, glib::OptionArg::String,
+                                &gettext("Add a todo, e.g. \"call bob tomorrow 3pm\""), Some(&gettext("TEXT")));
+    application.add_main_option("date", glib::Char::from(b'd'), glib::OptionFlags::NONE, glib::OptionArg::String,
+                                &gettext("Date of the added todo, or the date to show"), Some("YYYY-MM-DD"));
+    application.add_main_option("list", glib::Char::from(b'l'), glib::OptionFlags::NONE, glib::OptionArg::None,
+                                &gettext("Print the todos of --date, or the todos without date, and exit"), None);
+    application.add_main_option("daemon", glib::Char::from(0), glib::OptionFlags::NONE, glib::OptionArg::None,
+                                &gettext("Run in the background without opening the window"), None);
+    application.add_main_option("install-service", glib::Char::from(0), glib::OptionFlags::NONE, glib::OptionArg::None,
+                                &gettext("Write a systemd user unit, a D-Bus service file and an XDG autostart file for the background mode"), None);
+
+    application.connect_handle_local_options(|_, options| {
+        if options.contains("install-service") {
+            return match install_service_files() {
+                Ok(paths) => {
+                    for path in paths.iter() {
+                        println!("{}", fill_placeholders(&gettext("Written {}"), &[&path.display().to_string()]));
+                    }
+                    println!("{}", gettext("Run `systemctl --user enable --now rust-reminder.service` to start it with systemd"));
+                    0
+                }
+                Err(e) => {
+                    eprintln!("{}", fill_placeholders(&gettext("Error writing service files: {}"), &[&e.to_string()]));
+                    1
+                }
+            };
+        }
+        let date = match options.lookup::<String>("date").ok().flatten() {
+            Some(date) => match NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+                Ok(date) => Some(date),
+                Err(_) => {
+                    eprintln!("{}", fill_placeholders(&gettext("Invalid date: {}, expected YYYY-MM-DD"), &[&date]));
+                    return 1;
+                }
+            },
+            None => None,
+        };
+        if options.contains("list") {
+            let settings = Settings::load();
+            let store = match TodoStore::open(settings.database_path.clone()) {
+                Ok(store) => store,
+                Err(e) => {
+                    eprintln!("{}", fill_placeholders(&gettext("Error opening database: {}"), &[&e.to_string()]));
+                    return 1;
+                }
+            };
+            return match print_todos(&store, &settings, date) {
+                Ok(_) => 0,
+                Err(e) => {
+                    eprintln!("{}", fill_placeholders(&gettext("Database error: {}"), &[&e.to_string()]));
+                    1
+                }
+            };
+        }
+        return -1; // 继续交给主实例处理
+    });
+
+    // startup 只在主实例中触发一次, 之后的启动都只会触发 command-line. 窗口在需要显示时才创建
+    application.connect_startup(|app| {
+        let reminder = match Reminder::new() {
+            Ok(reminder) => reminder,
+            Err(e) => {
+                let message = fill_placeholders(&gettext("Error opening database: {}"), &[&e.to_string()]);
+                eprintln!("{}", message);
+                show_error_dialog(None, &message);
+                // 没有可用的数据库, 之后的启动请求都直接失败
+                app.connect_activate(|_| {});
+                app.connect_command_line(|_, _| 1);
+                app.quit();
+                return;
+            }
+        };
+        reminder.load_theme();
+        reminder.register_dbus_service(app);
+        reminder.start_alarm_scheduler(app);
+        reminder.start_tray_icon(app);
+        reminder.watch_external_changes();
+        if app.flags().contains(gio::ApplicationFlags::IS_SERVICE) {
+            reminder.run_in_background(app); // --gapplication-service, 由 systemd 或 D-Bus 激活启动
+        }
+
+        let reminder_clone = reminder.clone();
+        app.connect_activate(move |app| { reminder_clone.present(app) }); // 点击通知或 D-Bus 激活
+        app.connect_command_line(move |app, command_line| { reminder.handle_command_line(app, command_line) });
+    });
     application.run();
-}
\ No newline at end of file
+}
+
+// 直接读数据库, 不需要正在运行的实例. 顺序和窗口中的列表相同, 没有日期的 todo 按手动调整的顺序
+fn print_todos(store: &dyn TodoRepository, settings: &Settings, date: Option<NaiveDate>) -> StoreResult<()> {
+    for todo in store.find_todo(date.map(local_date), None)?.iter() {
+        let time = get_todo_time_text(todo, settings.time_format).unwrap_or_default();
+        println!("{}\t{}\t{}", todo.id, time, todo.content);
+    }
+    Ok(())
+}
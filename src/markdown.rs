@@ -0,0 +1,161 @@
+// 把 Markdown 的一个安全子集转换为 Pango markup: 粗体, 斜体, 行内代码, 链接和无序列表
+// 其余内容一律转义, 任意输入都不会生成非法的 markup
+
+const LINK_SCHEMES: [&str; 3] = ["http://", "https://", "mailto:"];
+
+pub fn escape_markup(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(c),
+        }
+    }
+    return result;
+}
+
+fn is_safe_link(url: &str) -> bool {
+    LINK_SCHEMES.iter().any(|scheme| url.to_lowercase().starts_with(scheme))
+}
+
+fn link(url: &str, text: &str) -> String {
+    format!("<a href=\"{}\">{}</a>", escape_markup(url), text)
+}
+
+// 在 chars[from..] 中找 delim, 找不到返回 None
+fn find_delim(chars: &[char], from: usize, delim: &[char]) -> Option<usize> {
+    (from..chars.len()).find(|i| chars[*i..].starts_with(delim))
+}
+
+fn render_inline(chars: &[char], allow_link: bool) -> String {
+    let mut result = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let prev_is_word = i > 0 && chars[i - 1].is_alphanumeric();
+
+        // 反斜杠转义 markdown 的符号
+        if c == '\\' && i + 1 < chars.len() && chars[i + 1].is_ascii_punctuation() {
+            result.push_str(&escape_markup(&chars[i + 1].to_string()));
+            i += 2;
+            continue;
+        }
+
+        if c == '`' {
+            if let Some(end) = find_delim(chars, i + 1, &['`']) {
+                let code: String = chars[i + 1..end].iter().collect();
+                result.push_str(&format!("<tt>{}</tt>", escape_markup(&code)));
+                i = end + 1;
+                continue;
+            }
+        }
+
+        if (c == '*' || c == '_') && chars.get(i + 1) == Some(&c) && !(c == '_' && prev_is_word) {
+            if let Some(end) = find_delim(chars, i + 2, &[c, c]).filter(|end| *end > i + 2) {
+                result.push_str(&format!("<b>{}</b>", render_inline(&chars[i + 2..end], allow_link)));
+                i = end + 2;
+                continue;
+            }
+        }
+
+        // 下划线出现在单词中间时不算斜体, 例如 snake_case
+        if (c == '*' || c == '_') && !(c == '_' && prev_is_word) && chars.get(i + 1).map_or(false, |n| !n.is_whitespace()) {
+            let end = find_delim(chars, i + 1, &[c])
+                .filter(|end| *end > i + 1 && !chars[*end - 1].is_whitespace())
+                .filter(|end| !(c == '_' && chars.get(*end + 1).map_or(false, |n| n.is_alphanumeric())));
+            if let Some(end) = end {
+                result.push_str(&format!("<i>{}</i>", render_inline(&chars[i + 1..end], allow_link)));
+                i = end + 1;
+                continue;
+            }
+        }
+
+        if c == '[' && allow_link {
+            if let Some(text_end) = find_delim(chars, i + 1, &[']', '(']) {
+                if let Some(url_end) = find_delim(chars, text_end + 2, &[')']) {
+                    let url: String = chars[text_end + 2..url_end].iter().collect();
+                    if is_safe_link(url.trim()) {
+                        result.push_str(&link(url.trim(), &render_inline(&chars[i + 1..text_end], false)));
+                        i = url_end + 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        // 没有写成 [text](url) 的网址也可以点击
+        if allow_link && !prev_is_word && LINK_SCHEMES[..2].iter().any(|scheme| chars[i..].starts_with(&scheme.chars().collect::<Vec<char>>())) {
+            let mut end = (i..chars.len()).find(|j| chars[*j].is_whitespace() || chars[*j] == '<' || chars[*j] == '>').unwrap_or(chars.len());
+            while end > i && ".,;:!?)]'\"".contains(chars[end - 1]) {
+                end -= 1; // 句末的标点不算网址的一部分
+            }
+            let url: String = chars[i..end].iter().collect();
+            result.push_str(&link(&url, &escape_markup(&url)));
+            i = end;
+            continue;
+        }
+
+        result.push_str(&escape_markup(&c.to_string()));
+        i += 1;
+    }
+    return result;
+}
+
+pub fn markdown_to_pango(text: &str) -> String {
+    let lines: Vec<String> = text.lines().map(|line| {
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+        let chars: Vec<char> = match trimmed.get(..2) {
+            Some("- ") | Some("* ") | Some("+ ") => trimmed[2..].chars().collect(),
+            _ => return render_inline(&line.chars().collect::<Vec<char>>(), true),
+        };
+        format!("{}• {}", indent, render_inline(&chars, true))
+    }).collect();
+    return lines.join("\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::markdown_to_pango;
+
+    #[test]
+    fn plain_text_is_escaped() {
+        assert_eq!(markdown_to_pango("a < b && c > \"d\""), "a &lt; b &amp;&amp; c &gt; &quot;d&quot;");
+        assert_eq!(markdown_to_pango("<b>not bold</b>"), "&lt;b&gt;not bold&lt;/b&gt;");
+    }
+
+    #[test]
+    fn emphasis_and_code() {
+        assert_eq!(markdown_to_pango("**bold** and *italic* and _also_"), "<b>bold</b> and <i>italic</i> and <i>also</i>");
+        assert_eq!(markdown_to_pango("__bold *nested*__"), "<b>bold <i>nested</i></b>");
+        assert_eq!(markdown_to_pango("run `rm -rf *.o <x>`"), "run <tt>rm -rf *.o &lt;x&gt;</tt>");
+    }
+
+    #[test]
+    fn unmatched_delimiters_stay_literal() {
+        assert_eq!(markdown_to_pango("**not closed"), "**not closed");
+        assert_eq!(markdown_to_pango("2 * 3 * 4"), "2 * 3 * 4");
+        assert_eq!(markdown_to_pango("snake_case_name"), "snake_case_name");
+        assert_eq!(markdown_to_pango("`open"), "`open");
+        assert_eq!(markdown_to_pango("\\*escaped\\*"), "*escaped*");
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(markdown_to_pango("[docs](https://example.com/a?b=1&c=2)"), "<a href=\"https://example.com/a?b=1&amp;c=2\">docs</a>");
+        assert_eq!(markdown_to_pango("see https://example.com."), "see <a href=\"https://example.com\">https://example.com</a>.");
+        assert_eq!(markdown_to_pango("[**bold** link](http://a.b)"), "<a href=\"http://a.b\"><b>bold</b> link</a>");
+        // 不安全的协议不生成链接
+        assert_eq!(markdown_to_pango("[x](javascript:alert(1))"), "[x](javascript:alert(1))");
+        assert_eq!(markdown_to_pango("[x](https://a.b\" onclick=\"y)"), "<a href=\"https://a.b&quot; onclick=&quot;y\">x</a>");
+    }
+
+    #[test]
+    fn bullet_lists() {
+        assert_eq!(markdown_to_pango("shopping:\n- milk\n  * **eggs**\n+ tea"), "shopping:\n• milk\n  • <b>eggs</b>\n• tea");
+    }
+}
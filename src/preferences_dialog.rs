@@ -0,0 +1,133 @@
+use chrono::{NaiveTime, Timelike};
+use gettextrs::gettext;
+use gtk::prelude::*;
+
+use crate::settings::{Settings, TimeFormat, WindowPosition};
+
+fn get_spin(lower: i32, upper: i32, value: i32) -> gtk::SpinButton {
+    let spin = gtk::SpinButton::with_range(lower as f64, upper as f64, 1f64);
+    spin.set_value(value as f64);
+    return spin;
+}
+
+fn get_combo(items: &[(&str, &str)], active_id: &str) -> gtk::ComboBoxText {
+    let combo = gtk::ComboBoxText::new();
+    for &(id, text) in items.iter() {
+        combo.append(Some(id), text);
+    }
+    combo.set_active_id(Some(active_id));
+    return combo;
+}
+
+fn get_label(text: &str) -> gtk::Label {
+    gtk::Label::builder().label(text).halign(gtk::Align::End).build()
+}
+
+// 取消时返回 None, 数据库路径为空时使用默认路径
+pub fn run_preferences_dialog(parent: Option<&gtk::Window>, settings: &Settings) -> Option<Settings> {
+    let dialog = gtk::Dialog::with_buttons(Some(&gettext("Preferences")), parent, gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+                                           &[(&gettext("Cancel"), gtk::ResponseType::Cancel), (&gettext("Save"), gtk::ResponseType::Accept)]);
+    dialog.set_default_response(gtk::ResponseType::Accept);
+
+    let alarm_check = gtk::CheckButton::with_label(&gettext("Remind"));
+    alarm_check.set_active(settings.default_alarm_minutes.is_some());
+    let alarm_spin = get_spin(0, 10080, settings.default_alarm_minutes.unwrap_or(15));
+    alarm_spin.set_sensitive(alarm_check.is_active());
+    let alarm_spin_clone = alarm_spin.clone();
+    alarm_check.connect_toggled(move |c| {
+        alarm_spin_clone.set_sensitive(c.is_active());
+    });
+    let alarm_box = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+    alarm_box.pack_start(&alarm_check, false, false, 0);
+    alarm_box.pack_start(&alarm_spin, false, false, 0);
+    alarm_box.pack_start(&gtk::Label::new(Some(&gettext("minutes before due"))), false, false, 0);
+
+    let hour_spin = get_spin(0, 23, settings.default_time.hour() as i32);
+    let minute_spin = get_spin(0, 59, settings.default_time.minute() as i32);
+    minute_spin.connect_output(|x| {
+        x.set_text(&format!("{:02}", x.value()));
+        return gtk::Inhibit(true);
+    });
+    let time_box = gtk::Box::new(gtk::Orientation::Horizontal, 3);
+    time_box.pack_start(&hour_spin, false, false, 0);
+    time_box.pack_start(&gtk::Label::new(Some(":")), false, false, 0);
+    time_box.pack_start(&minute_spin, false, false, 0);
+
+    let time_format_combo = get_combo(&[("24h", &gettext("24-hour")), ("12h", &gettext("12-hour"))], match settings.time_format {
+        TimeFormat::Hour24 => "24h",
+        TimeFormat::Hour12 => "12h",
+    });
+    let position_combo = get_combo(&[("mouse", &gettext("Under the mouse")), ("center", &gettext("Center of the screen")), ("none", &gettext("Decided by the window manager"))],
+                                   match settings.window_position {
+                                       WindowPosition::Mouse => "mouse",
+                                       WindowPosition::Center => "center",
+                                       WindowPosition::None => "none",
+                                   });
+
+    let completed_check = gtk::CheckButton::with_label(&gettext("Show"));
+    completed_check.set_active(settings.show_completed);
+
+    let window_width_spin = get_spin(300, 4096, settings.window_width);
+    let calendar_width_spin = get_spin(150, 1024, settings.calendar_width);
+    let min_year_spin = get_spin(1900, 2999, settings.min_year);
+    let max_year_spin = get_spin(1900, 2999, settings.max_year);
+    let year_box = gtk::Box::new(gtk::Orientation::Horizontal, 3);
+    year_box.pack_start(&min_year_spin, false, false, 0);
+    year_box.pack_start(&gtk::Label::new(Some("–")), false, false, 0);
+    year_box.pack_start(&max_year_spin, false, false, 0);
+
+    let database_entry = gtk::Entry::builder().text(settings.database_path.as_deref().unwrap_or(""))
+        .placeholder_text("~/.config/rust-reminder/todo.db").tooltip_text(&gettext("Switches to this database when saved, the old path is kept if it cannot be opened"))
+        .activates_default(true).hexpand(true).build();
+
+    let rows: Vec<(&str, gtk::Widget)> = vec![
+        (N_!("Default reminder"), alarm_box.upcast()),
+        (N_!("Default time"), time_box.upcast()),
+        (N_!("Time format"), time_format_combo.clone().upcast()),
+        (N_!("Completed todos"), completed_check.clone().upcast()),
+        (N_!("Window position"), position_combo.clone().upcast()),
+        (N_!("Window width"), window_width_spin.clone().upcast()),
+        (N_!("Calendar width"), calendar_width_spin.clone().upcast()),
+        (N_!("Year range"), year_box.upcast()),
+        (N_!("Database"), database_entry.clone().upcast()),
+    ];
+    let grid = gtk::Grid::builder().row_spacing(6).column_spacing(6).border_width(6).build();
+    for (i, (label, widget)) in rows.iter().enumerate() {
+        grid.attach(&get_label(&gettext(*label)), 0, i as i32, 1, 1);
+        widget.set_halign(gtk::Align::Start);
+        grid.attach(widget, 1, i as i32, 1, 1);
+    }
+    database_entry.set_halign(gtk::Align::Fill);
+    dialog.content_area().pack_start(&grid, true, true, 0);
+    dialog.show_all();
+
+    let response = dialog.run();
+    let database_path = database_entry.text().trim().to_string();
+    let min_year = min_year_spin.value_as_int();
+    let max_year = max_year_spin.value_as_int();
+    let new_settings = Settings {
+        database_path: if database_path.is_empty() { None } else { Some(database_path) },
+        window_width: window_width_spin.value_as_int(),
+        calendar_width: calendar_width_spin.value_as_int(),
+        window_position: match position_combo.active_id().as_deref() {
+            Some("center") => WindowPosition::Center,
+            Some("none") => WindowPosition::None,
+            _ => WindowPosition::Mouse,
+        },
+        min_year: min_year.min(max_year),
+        max_year: min_year.max(max_year),
+        default_alarm_minutes: if alarm_check.is_active() { Some(alarm_spin.value_as_int()) } else { None },
+        time_format: match time_format_combo.active_id().as_deref() {
+            Some("12h") => TimeFormat::Hour12,
+            _ => TimeFormat::Hour24,
+        },
+        default_time: NaiveTime::from_hms(hour_spin.value_as_int() as u32, minute_spin.value_as_int() as u32, 0),
+        show_completed: completed_check.is_active(),
+    };
+    dialog.close();
+
+    if response != gtk::ResponseType::Accept {
+        return None;
+    }
+    Some(new_settings)
+}
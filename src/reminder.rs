@@ -1,26 +1,41 @@
 use std::borrow::Borrow;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::ops::Deref;
 use std::rc::Rc;
 
-use chrono::{Date, Datelike, Local, TimeZone};
-use diesel::SqliteConnection;
-use gtk::{Application, Calendar, ListBox};
+use chrono::{Date, Datelike, Duration, Local, NaiveDate};
+use gettextrs::gettext;
+use gtk::{Application, Calendar, gdk, gio, glib, IconSize, ListBox};
 use gtk::prelude::*;
 
-use crate::models::{db_del_todo, db_find_todo, db_get_exists_day, db_new_todo, db_update_todo, establish_connection, NewTodo, Todo};
-use crate::reminder_edit_dialog::ReminderEditDialog;
-use crate::utils::{get_icon_view, get_todo_row_view};
+use reminder_core::{local_date, NewAttachment, NewTodo, OpenError, PRIORITY_NONE, StoreResult, Todo, TodoList, TodoRepository, TodoStore, wall_to_utc};
+
+use crate::alarm_scheduler::start_alarm_scheduler;
+use crate::date_parser::parse_todo_on;
+use crate::dbus_service::{emit_todos_changed, handle_method_call, register};
+use crate::i18n::fill_placeholders;
+use crate::ical::{export_todos, import_todos};
+use crate::list_edit_dialog::run_list_edit_dialog;
+use crate::preferences_dialog::run_preferences_dialog;
+use crate::reminder_edit_dialog::{ReminderEditDialog, TodoStart, TodoTime};
+use crate::settings::Settings;
+use crate::shortcuts::{find_shortcut_action, get_shortcuts_window};
+use crate::theme::load_css;
+use crate::tray_icon::{TrayAction, TrayIcon};
+use crate::utils::{ask_confirm, format_date, format_date_time, get_action_button, get_calendar_date_at, get_dropped_todo_id, get_list_row_view, get_row_list, get_row_todo, get_todo_drag_targets, get_todo_row_view, get_todo_time_text, get_uri_drop_targets, get_week_start, is_todo_drag, show_error_dialog};
+
+const TRAY_TODO_COUNT: usize = 5;
+const DATA_VERSION_CHECK_SECONDS: u32 = 2;
 
 pub struct ResetDateButton {
-    reset_date_btn: gtk::IconView,
+    reset_date_btn: gtk::Button,
     current_date_label: gtk::Label,
 }
 
 impl ResetDateButton {
     fn new() -> Self {
         ResetDateButton {
-            reset_date_btn: get_icon_view(&["edit-undo"]).unwrap(),
+            reset_date_btn: get_action_button("edit-undo", "app.reset-date", &gettext("Show todos without date, drop a todo here to clear its date")),
             current_date_label: gtk::Label::new(None),
         }
     }
@@ -36,65 +51,142 @@ impl ResetDateButton {
     }
 
     fn set_date(&self, date: Date<Local>) {
-        self.current_date_label.set_label(&date.format("%Y-%m-%d").to_string());
+        self.current_date_label.set_label(&format_date(date.naive_local()));
     }
 }
 
 #[derive(Clone)]
 pub struct Reminder {
-    db_conn: Rc<SqliteConnection>,
+    store: Rc<RefCell<Rc<dyn TodoRepository>>>, // 修改数据库路径后会换成新的存储
     calendar: Rc<gtk::Calendar>,
-    todo_edit_panel_button: Vec<(&'static str, &'static dyn Fn(&Self))>,
+    todo_edit_panel_button: Vec<(&'static str, &'static str, &'static str)>,
+    actions: Vec<(&'static str, &'static dyn Fn(&Self))>,
     todo_msg_list: Rc<gtk::ListBox>,
     current_date: Rc<RefCell<Option<Date<Local>>>>,
     reset_date_btn: Rc<ResetDateButton>,
+    search_bar: Rc<gtk::SearchBar>,
+    search_entry: Rc<gtk::SearchEntry>,
+    quick_add_entry: Rc<gtk::Entry>,
+    list_sidebar: Rc<gtk::ListBox>,
+    current_list: Rc<RefCell<Option<i32>>>, // None 表示显示全部清单
+    dbus_connection: Rc<RefCell<Option<gio::DBusConnection>>>,
+    background: Rc<Cell<bool>>, // 关闭窗口后继续在后台运行
+    tray_icon: Rc<RefCell<Option<TrayIcon>>>,
+    settings: Rc<RefCell<Settings>>,
+    css_monitor: Rc<RefCell<Option<gio::FileMonitor>>>,
 }
 
 impl Reminder {
-    pub fn new() -> Reminder {
+    pub fn new() -> Result<Reminder, OpenError> {
+        let settings = Settings::load();
+        let store = TodoStore::open(settings.database_path.clone())?;
+        return Ok(Reminder::with_repository(Rc::new(store), settings));
+    }
+
+    pub fn with_repository(store: Rc<dyn TodoRepository>, settings: Settings) -> Reminder {
         return Reminder {
-            db_conn: Rc::new(establish_connection(None)),
+            store: Rc::new(RefCell::new(store)),
             calendar: Rc::new(gtk::Calendar::new()),
             todo_edit_panel_button: vec![
-                ("list-add", &Reminder::todo_add_callback),
-                ("list-remove", &Reminder::todo_remove_callback),
-                ("document-page-setup", &Reminder::todo_edit_callback),
+                ("list-add", "app.new-todo", N_!("New todo")),
+                ("object-select-symbolic", "app.complete", N_!("Mark selected todos as done or not done")),
+                ("list-remove", "app.delete", N_!("Remove selected todos")),
+                ("document-page-setup", "app.edit", N_!("Edit selected todo")),
+            ],
+            actions: vec![
+                ("new-todo", &Reminder::todo_add_callback),
+                ("edit", &Reminder::todo_edit_callback),
+                ("delete", &Reminder::todo_remove_callback),
+                ("complete", &Reminder::todo_complete_callback),
+                ("move-up", &Reminder::todo_move_up_callback),
+                ("move-down", &Reminder::todo_move_down_callback),
+                ("today", &Reminder::goto_today),
+                ("reset-date", &Reminder::clear_date),
+                ("prev-day", &Reminder::select_prev_day),
+                ("next-day", &Reminder::select_next_day),
+                ("search", &Reminder::toggle_search),
+                ("shortcuts", &Reminder::show_shortcuts),
+                ("import", &Reminder::import_callback),
+                ("export", &Reminder::export_callback),
+                ("new-list", &Reminder::list_add_callback),
+                ("edit-list", &Reminder::list_edit_callback),
+                ("delete-list", &Reminder::list_remove_callback),
+                ("preferences", &Reminder::preferences_callback),
             ],
             todo_msg_list: Rc::new(gtk::ListBox::new()),
             current_date: Rc::new(RefCell::new(None)),
             reset_date_btn: Rc::new(ResetDateButton::new()),
+            search_bar: Rc::new(gtk::SearchBar::new()),
+            search_entry: Rc::new(gtk::SearchEntry::new()),
+            quick_add_entry: Rc::new(gtk::Entry::builder().placeholder_text(&gettext("Add a todo, e.g. call bob tomorrow 3pm")).build()),
+            list_sidebar: Rc::new(gtk::ListBox::new()),
+            current_list: Rc::new(RefCell::new(None)),
+            dbus_connection: Rc::new(RefCell::new(None)),
+            background: Rc::new(Cell::new(false)),
+            tray_icon: Rc::new(RefCell::new(None)),
+            settings: Rc::new(RefCell::new(settings)),
+            css_monitor: Rc::new(RefCell::new(None)),
         };
     }
 
     fn todo_add_callback(&self) {
         let date = self.current_date.deref().borrow().clone();
 
-        let todo_add_dialog = ReminderEditDialog::new("New todo", date.is_some());
+        let settings = self.settings.deref().borrow().clone();
+        let todo_add_dialog = ReminderEditDialog::new(&gettext("New todo"), date.is_some());
+        todo_add_dialog.apply_settings(&settings);
         if let Some(date) = date {
-            todo_add_dialog.set_time(date.and_time(Local::now().time()).unwrap());
+            todo_add_dialog.set_time(TodoTime {
+                time: date.naive_local().and_time(settings.default_time), // 取消全天时显示的时间
+                timezone: None,
+                all_day: true, // 在某一天下新建的 todo 默认是全天的
+            });
+            todo_add_dialog.set_alarms(settings.default_alarms());
         }
 
+        todo_add_dialog.set_lists(&self.all_list(), *self.current_list.deref().borrow()); // 默认放到当前选中的清单
         todo_add_dialog.show();
 
         let self_clone = self.clone();
-        todo_add_dialog.connect_hide(move |save_todo, content, time| {
+        todo_add_dialog.connect_hide(move |save_todo, edit| {
             if save_todo {
-                let todo = match time {
+                let start_time = edit.start.as_ref().map(|start| start.start_time(edit.time.as_ref()));
+                let hide_until_start = edit.start.map_or(false, |start| start.hide_until_start);
+                let todo = match edit.time {
                     Some(time) => {
                         NewTodo {
-                            content: content,
-                            expire_time: Some(time.naive_local()),
+                            content: edit.content,
+                            expire_time: Some(time.expire_time()),
+                            timezone: time.timezone,
+                            all_day: time.all_day,
+                            start_time: start_time,
+                            hide_until_start: hide_until_start,
+                            notes: edit.notes,
+                            list_id: edit.list_id,
+                            priority: edit.priority,
                         }
                     }
                     None => {
                         NewTodo {
-                            content: content,
+                            content: edit.content,
                             expire_time: None,
+                            timezone: None,
+                            all_day: false,
+                            start_time: start_time,
+                            hide_until_start: hide_until_start,
+                            notes: edit.notes,
+                            list_id: edit.list_id,
+                            priority: edit.priority,
                         }
                     }
                 };
 
-                db_new_todo(self_clone.db_conn.borrow(), &todo);
+                let result = self_clone.store().new_todo(&todo).and_then(|todo_id| {
+                    self_clone.store().set_alarm(todo_id, &edit.alarms)?;
+                    self_clone.store().set_attachment(todo_id, &edit.attachments)
+                });
+                self_clone.check_store(result);
+                self_clone.notify_todos_changed();
                 self_clone.todo_refresh()
             }
         });
@@ -108,7 +200,27 @@ impl Reminder {
             }
         });
 
-        db_del_todo(self.db_conn.deref(), &todo_id);
+        self.check_store(self.store().del_todo(&todo_id));
+        self.notify_todos_changed();
+        self.todo_refresh();
+    }
+
+    // 选中的 todo 都已完成时改回未完成, 否则全部标记为已完成
+    fn todo_complete_callback(&self) {
+        let mut todos = Vec::<Todo>::new();
+        self.todo_msg_list.selected_foreach(|_, r| {
+            if let Some(todo) = get_row_todo(r) {
+                todos.push(todo);
+            }
+        });
+        let completed = !todos.iter().all(|todo| todo.completed);
+
+        for todo in todos.iter() {
+            if self.check_store(self.store().set_completed(todo.id, completed)).is_none() {
+                break;
+            }
+        }
+        self.notify_todos_changed();
         self.todo_refresh();
     }
 
@@ -132,113 +244,828 @@ impl Reminder {
         }
         let todo = todo.unwrap();
 
-        let todo_add_dialog = ReminderEditDialog::new("Edit todo", todo.expire_time.is_some());
-        if let Some(time) = todo.expire_time {
-            todo_add_dialog.set_time(Local.from_local_datetime(&time).unwrap());
+        let todo_add_dialog = ReminderEditDialog::new(&gettext("Edit todo"), todo.expire_time.is_some());
+        todo_add_dialog.apply_settings(&self.settings.deref().borrow());
+        if let Some(time) = TodoTime::from_todo(&todo) {
+            todo_add_dialog.set_time(time);
         }
+        todo_add_dialog.set_start(TodoStart::from_todo(&todo));
+        // 读不出提醒和附件时不打开, 以免保存时把它们清空
+        let (alarms, attachments) = match (self.check_store(self.store().find_alarm(todo.id)), self.check_store(self.store().find_attachment(todo.id))) {
+            (Some(alarms), Some(attachments)) => (alarms, attachments),
+            _ => return,
+        };
+        todo_add_dialog.set_alarms(alarms.iter().map(|alarm| alarm.to_new_alarm()).collect());
+        todo_add_dialog.set_attachments(attachments.iter().map(|attachment| attachment.to_new_attachment()).collect());
 
-        todo_add_dialog.set_content(todo.content);
+        todo_add_dialog.set_content(todo.content.clone());
+        todo_add_dialog.set_notes(todo.notes.clone());
+        todo_add_dialog.set_lists(&self.all_list(), todo.list_id);
+        todo_add_dialog.set_priority(todo.priority);
         todo_add_dialog.show();
 
         let self_clone = self.clone();
-        todo_add_dialog.connect_hide(move |save_todo, content, time| {
+        todo_add_dialog.connect_hide(move |save_todo, edit| {
             if save_todo {
-                let todo = match time {
+                let start_time = edit.start.as_ref().map(|start| start.start_time(edit.time.as_ref()));
+                let hide_until_start = edit.start.map_or(false, |start| start.hide_until_start);
+                let todo = match edit.time {
                     Some(time) => {
+                        let same_day = todo.local_date() == Some(time.local_date());
                         Todo {
                             id: todo.id,
-                            content: content,
-                            expire_time: Some(time.naive_local()),
+                            content: edit.content,
+                            expire_time: Some(time.expire_time()),
+                            position: if same_day { todo.position } else { 0 }, // 换到别的日期后不再保留原来的排序
+                            timezone: time.timezone,
+                            all_day: time.all_day,
+                            start_time: start_time,
+                            hide_until_start: hide_until_start,
+                            notes: edit.notes,
+                            list_id: edit.list_id,
+                            completed: todo.completed,
+                            priority: edit.priority,
                         }
                     }
                     None => {
                         Todo {
                             id: todo.id,
-                            content: content,
+                            content: edit.content,
                             expire_time: None,
+                            position: if todo.expire_time.is_none() { todo.position } else { self_clone.check_store(self_clone.store().next_undated_position()).unwrap_or(todo.position) },
+                            timezone: None,
+                            all_day: false,
+                            start_time: start_time,
+                            hide_until_start: hide_until_start,
+                            notes: edit.notes,
+                            list_id: edit.list_id,
+                            completed: todo.completed,
+                            priority: edit.priority,
                         }
                     }
                 };
 
-                db_update_todo(self_clone.db_conn.borrow(), &todo);
+                let result = self_clone.store().update_todo(&todo)
+                    .and_then(|_| self_clone.store().set_alarm(todo.id, &edit.alarms))
+                    .and_then(|_| self_clone.store().set_attachment(todo.id, &edit.attachments));
+                self_clone.check_store(result);
+                self_clone.notify_todos_changed();
                 self_clone.todo_refresh()
             }
         });
     }
 
+    fn todo_quick_add_callback(&self) {
+        let text = self.quick_add_entry.text().trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+
+        let date = self.current_date.deref().borrow().map(|date| date.naive_local());
+        if self.todo_add_from_text(text, date).is_some() {
+            self.quick_add_entry.set_text(""); // 保存失败时保留输入的文字
+        }
+        self.todo_refresh();
+    }
+
+    fn todo_add_from_text(&self, text: String, date: Option<NaiveDate>) -> Option<i32> {
+        let result = add_todo_from_text(self.store().deref(), &self.settings.deref().borrow(), text, date, *self.current_list.deref().borrow());
+        let todo_id = self.check_store(result);
+        self.notify_todos_changed();
+        return todo_id;
+    }
+
+    pub fn register_dbus_service(&self, application: &Application) {
+        let connection = match application.dbus_connection() {
+            Some(connection) => connection,
+            None => return,
+        };
+
+        let self_clone = self.clone();
+        let result = register(&connection, move |method, parameters, invocation| {
+            let changed = handle_method_call(self_clone.store().deref(), &self_clone.settings.deref().borrow(), method, parameters, invocation);
+            if changed {
+                self_clone.todo_refresh();
+                self_clone.notify_todos_changed();
+            }
+        });
+        match result {
+            Ok(_) => *self.dbus_connection.deref().borrow_mut() = Some(connection),
+            Err(e) => eprintln!("Error registering D-Bus service: {}", e),
+        }
+    }
+
+    fn notify_todos_changed(&self) {
+        if let Some(connection) = self.dbus_connection.deref().borrow().as_ref() {
+            emit_todos_changed(connection);
+        }
+        self.tray_refresh();
+    }
+
+    pub fn start_tray_icon(&self, application: &Application) {
+        let connection = match application.dbus_connection() {
+            Some(connection) => connection,
+            None => return,
+        };
+
+        let self_clone = self.clone();
+        let application_clone = application.clone();
+        match TrayIcon::new(&connection, move |action| self_clone.tray_action(&application_clone, action)) {
+            Ok(tray_icon) => *self.tray_icon.deref().borrow_mut() = Some(tray_icon),
+            Err(e) => {
+                eprintln!("Error creating tray icon: {}", e);
+                return;
+            }
+        }
+        self.tray_refresh();
+
+        let self_clone = self.clone();
+        glib::timeout_add_seconds_local(60, move || {
+            self_clone.tray_refresh(); // 过了零点后今天的 todo 会变
+            glib::Continue(true)
+        });
+    }
+
+    // 托盘显示今天到期和已经过期的 todo 数量, 菜单中列出前几个
+    fn tray_refresh(&self) {
+        let tray_icon = self.tray_icon.deref().borrow();
+        let tray_icon = match tray_icon.as_ref() {
+            Some(tray_icon) => tray_icon,
+            None => return,
+        };
+
+        let today = Local::now().date();
+        // 托盘每分钟刷新一次, 出错时只输出日志, 不弹窗
+        let (all_todos, today_todos) = match self.store().all_todo().and_then(|all_todos| Ok((all_todos, self.store().find_todo(Some(today), None)?))) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Error refreshing tray icon: {}", e);
+                return;
+            }
+        };
+
+        // 已完成的 todo 不算到期, 也不显示在菜单中
+        let date_pattern = gettext("%m-%d");
+        let overdue: Vec<(i32, String)> = all_todos.into_iter()
+            .filter(|todo| !todo.completed && todo.local_date().map_or(false, |date| date < today.naive_local()))
+            .map(|todo| (todo.id, format!("{}  {}", format_date_time(&todo.local_date().unwrap().and_hms(0, 0, 0), &date_pattern), todo.content)))
+            .collect();
+        // 和 todo_refresh 使用同一个按天查询
+        let time_format = self.settings.deref().borrow().time_format;
+        let today: Vec<(i32, String)> = today_todos.into_iter().filter(|todo| !todo.completed).map(|todo| {
+            let text = match get_todo_time_text(&todo, time_format) {
+                Some(time) => format!("{}  {}", time, todo.content),
+                None => todo.content.clone(),
+            };
+            (todo.id, text)
+        }).collect();
+
+        // 菜单中一共最多列出 TRAY_TODO_COUNT 个, 优先列出过期的
+        let overdue_count = overdue.len().min(TRAY_TODO_COUNT);
+        let today_count = today.len().min(TRAY_TODO_COUNT - overdue_count);
+        tray_icon.update(overdue.len() + today.len(), &overdue[..overdue_count], &today[..today_count]);
+    }
+
+    fn tray_action(&self, application: &Application, action: TrayAction) {
+        match action {
+            TrayAction::Open => self.present(application),
+            TrayAction::QuickAdd => {
+                self.present(application);
+                self.quick_add_entry.grab_focus();
+            }
+            TrayAction::ShowTodo(todo_id) => {
+                let todo = match self.check_store(self.store().get_todo(todo_id)).flatten() {
+                    Some(todo) => todo,
+                    None => return,
+                };
+                self.present(application);
+                if self.current_list.deref().borrow().map_or(false, |list_id| Some(list_id) != todo.list_id) {
+                    *self.current_list.deref().borrow_mut() = None; // 不在当前清单中, 切换到 "All"
+                    self.list_refresh();
+                }
+                if let Some(date) = todo.local_date() {
+                    self.select_date(local_date(date));
+                }
+                self.select_todo(todo_id);
+            }
+            TrayAction::Quit => application.quit(),
+        }
+    }
+
+    // 再次启动时由已经运行的实例处理命令行, 例如 rust-reminder --add "call bob" --date 2026-10-20
+    pub fn handle_command_line(&self, application: &Application, command_line: &gio::ApplicationCommandLine) -> i32 {
+        let options = command_line.options_dict();
+        if options.contains("daemon") {
+            self.run_in_background(application);
+        } else {
+            self.present(application);
+        }
+
+        // 日期的格式已经在 main 的 handle-local-options 中检查过
+        let date = options.lookup::<String>("date").ok().flatten()
+            .and_then(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok());
+
+        if let Some(text) = options.lookup::<String>("add").ok().flatten() {
+            if !text.trim().is_empty() {
+                self.todo_add_from_text(text.trim().to_string(), date);
+            }
+        }
+        if let Some(date) = date {
+            self.select_date(local_date(date));
+        }
+        self.todo_refresh();
+        return 0;
+    }
+
+    // 第一次显示时才创建窗口
+    pub fn present(&self, application: &Application) {
+        match self.window() {
+            Some(window) => window.present(),
+            None => self.build_ui(application),
+        }
+    }
+
+    // 不显示窗口, 没有窗口时 application 也不会退出
+    pub fn run_in_background(&self, application: &Application) {
+        if !self.background.get() {
+            self.background.set(true);
+            application.hold();
+        }
+    }
+
+    pub fn start_alarm_scheduler(&self, application: &Application) {
+        start_alarm_scheduler(application, self.store.clone(), self.settings.clone());
+    }
+
+    fn todo_inline_edit_callback(&self, row: &gtk::ListBoxRow) {
+        let grid = match row.child().and_then(|c| c.downcast::<gtk::Grid>().ok()) {
+            Some(grid) => grid,
+            None => return,
+        };
+        let todo = unsafe {
+            match grid.data::<Todo>("todo") {
+                Some(todo) => todo.as_ref().clone(),
+                None => return,
+            }
+        };
+        let content_label = match grid.child_at(1, 0) {
+            Some(label) => label,
+            None => return,
+        };
+
+        let entry = gtk::Entry::builder().text(&todo.content).hexpand(true).build();
+        grid.remove(&content_label);
+        grid.attach(&entry, 1, 0, 1, 1);
+        entry.show();
+        entry.grab_focus();
+
+        let self_clone = self.clone();
+        entry.connect_activate(move |e| {
+            let content = e.text().to_string();
+            if !content.trim().is_empty() {
+                let mut todo = todo.clone();
+                todo.content = content;
+                self_clone.check_store(self_clone.store().update_todo(&todo));
+                self_clone.notify_todos_changed();
+            }
+            self_clone.todo_refresh();
+        });
+
+        let self_clone = self.clone();
+        entry.connect_key_press_event(move |_, e| {
+            if e.keyval() == gtk::gdk::keys::constants::Escape {
+                self_clone.todo_refresh(); // 重新生成列表即可还原
+                return gtk::Inhibit(true);
+            }
+            return gtk::Inhibit(false);
+        });
+    }
+
+    fn todo_move_to_date(&self, todo_id: i32, date: Option<NaiveDate>) {
+        let todo = match self.check_store(self.store().get_todo(todo_id)).flatten() {
+            Some(todo) => todo,
+            None => return,
+        };
+
+        if date == todo.local_date() {
+            return;
+        }
+        let todo = todo.moved_to_date(date);
+        let position = match todo.expire_time {
+            Some(_) => 0,
+            None => match self.check_store(self.store().next_undated_position()) {
+                Some(position) => position,
+                None => return,
+            },
+        };
+
+        let result = self.store().update_todo(&Todo { position: position, ..todo });
+        self.check_store(result);
+        self.notify_todos_changed();
+        self.todo_refresh_later();
+    }
+
+    fn todo_reorder(&self, todo_id: i32, y: i32) {
+        let target_row = match self.todo_msg_list.row_at_y(y) {
+            Some(row) => row,
+            None => return,
+        };
+        let target_id = match get_row_todo(&target_row) {
+            Some(todo) if todo.id != todo_id => todo.id,
+            _ => return,
+        };
+
+        let mut todo_id_list: Vec<i32> = self.get_todo_id_list().into_iter().filter(|id| *id != todo_id).collect();
+
+        let mut index = todo_id_list.iter().position(|id| *id == target_id).unwrap();
+        let allocation = target_row.allocation();
+        if y > allocation.y() + allocation.height() / 2 { // 放在目标的下半部分时插到它后面
+            index += 1;
+        }
+        todo_id_list.insert(index, todo_id);
+
+        self.check_store(self.store().reorder_todo(&todo_id_list));
+        self.notify_todos_changed();
+        self.todo_refresh_later();
+    }
+
+    // 拖到某一行上的文件或链接作为该 todo 的附件
+    fn todo_attach_uris(&self, y: i32, uris: Vec<String>) {
+        let todo = match self.todo_msg_list.row_at_y(y).and_then(|r| get_row_todo(&r)) {
+            Some(todo) => todo,
+            None => return,
+        };
+
+        for uri in uris.into_iter() {
+            if self.check_store(self.store().add_attachment(todo.id, &NewAttachment { uri: uri })).is_none() {
+                break;
+            }
+        }
+        self.notify_todos_changed();
+        self.todo_refresh_later();
+    }
+
+    fn todo_move_callback(&self, offset: i32) {
+        let todo = match self.todo_msg_list.selected_row().and_then(|r| get_row_todo(&r)) {
+            Some(todo) => todo,
+            None => return,
+        };
+
+        // 搜索时只和看得见的相邻 todo 交换, 被过滤掉的 todo 保持原来的位置
+        let visible_id_list = self.get_visible_todo_id_list();
+        let index = visible_id_list.iter().position(|id| *id == todo.id).unwrap() as i32;
+        if index + offset < 0 || index + offset >= visible_id_list.len() as i32 {
+            return;
+        }
+        let other_id = visible_id_list[(index + offset) as usize];
+
+        let mut todo_id_list = self.get_todo_id_list();
+        let index = todo_id_list.iter().position(|id| *id == todo.id).unwrap();
+        let other_index = todo_id_list.iter().position(|id| *id == other_id).unwrap();
+        todo_id_list.swap(index, other_index);
+
+        self.check_store(self.store().reorder_todo(&todo_id_list));
+        self.notify_todos_changed();
+        self.todo_refresh();
+        self.select_todo(todo.id);
+    }
+
+    fn todo_move_up_callback(&self) {
+        self.todo_move_callback(-1);
+    }
+
+    fn todo_move_down_callback(&self) {
+        self.todo_move_callback(1);
+    }
+
+    fn get_todo_id_list(&self) -> Vec<i32> {
+        self.todo_msg_list.children().iter()
+            .filter_map(|r| get_row_todo(r.downcast_ref::<gtk::ListBoxRow>()?))
+            .map(|todo| todo.id).collect()
+    }
+
+    // 不包括被搜索过滤掉的行
+    fn get_visible_todo_id_list(&self) -> Vec<i32> {
+        self.todo_msg_list.children().iter()
+            .filter(|r| r.is_child_visible())
+            .filter_map(|r| get_row_todo(r.downcast_ref::<gtk::ListBoxRow>()?))
+            .map(|todo| todo.id).collect()
+    }
+
+    fn select_todo(&self, todo_id: i32) {
+        for r in self.todo_msg_list.children().iter() {
+            if let Some(row) = r.downcast_ref::<gtk::ListBoxRow>() {
+                if get_row_todo(row).map(|todo| todo.id) == Some(todo_id) {
+                    self.todo_msg_list.select_row(Some(row));
+                    row.grab_focus();
+                }
+            }
+        }
+    }
+
+    fn todo_refresh_later(&self) {
+        let self_clone = self.clone();
+        glib::idle_add_local_once(move || {
+            self_clone.todo_refresh(); // 拖拽还没结束, 不能马上把拖拽源销毁
+        });
+    }
+
     fn todo_refresh(&self) {
+        let selected_id: Vec<i32> = self.todo_msg_list.selected_rows().iter().filter_map(|r| get_row_todo(r)).map(|todo| todo.id).collect();
         self.todo_msg_list.foreach(|r| {
             self.todo_msg_list.remove(r);
         }); // clear list items
 
-        let todo_list = match self.current_date.deref().borrow().deref() {
-            Some(date) => {
-                db_find_todo(self.db_conn.deref(), Some(date.clone()))
-            }
-            None => {
-                db_find_todo(self.db_conn.deref(), None)
-            }
+        let list_id = *self.current_list.deref().borrow();
+        let current_date = self.current_date.deref().borrow().clone();
+        let show_completed = self.settings.deref().borrow().show_completed;
+        let result = self.store().find_todo(current_date, list_id).and_then(|todo_list| {
+            let todo_list: Vec<Todo> = todo_list.into_iter().filter(|todo| show_completed || !todo.completed).collect();
+            let attachment_count = todo_list.iter().map(|todo| self.store().find_attachment(todo.id).map(|a| a.len())).collect::<StoreResult<Vec<usize>>>()?;
+            Ok((todo_list, attachment_count))
+        });
+        let (todo_list, attachment_count) = match self.check_store(result) {
+            Some(result) => result,
+            None => return,
         };
 
-        for todo in todo_list.iter() {
-            let todo = get_todo_row_view(todo);
+        // 显示全部清单时用颜色区分 todo 所在的清单
+        let all_list = if list_id.is_none() { self.all_list() } else { vec![] };
+        let time_format = self.settings.deref().borrow().time_format;
+        for (todo, attachment_count) in todo_list.iter().zip(attachment_count) {
+            let color = todo.list_id.and_then(|id| all_list.iter().find(|l| l.id == id)).map(|l| l.color.as_str());
+            let todo = get_todo_row_view(todo, attachment_count, color, time_format);
             self.todo_msg_list.add(&todo);
         }
 
         self.refresh_marked_day();
         self.todo_msg_list.show_all();
+
+        // 刷新后保留原来选中的 todo
+        for r in self.todo_msg_list.children().iter() {
+            if let Some(row) = r.downcast_ref::<gtk::ListBoxRow>() {
+                if get_row_todo(row).map_or(false, |todo| selected_id.contains(&todo.id)) {
+                    self.todo_msg_list.select_row(Some(row));
+                }
+            }
+        }
+    }
+
+    // 自带的样式和 ~/.config/rust-reminder/style.css, 后者修改后马上生效
+    pub fn load_theme(&self) {
+        *self.css_monitor.deref().borrow_mut() = load_css();
+    }
+
+    // 命令行或另一个实例修改数据库后自动刷新
+    pub fn watch_external_changes(&self) {
+        let data_version = Cell::new(self.store().data_version().unwrap_or(0));
+        let self_clone = self.clone();
+        glib::timeout_add_seconds_local(DATA_VERSION_CHECK_SECONDS, move || {
+            match self_clone.store().data_version() {
+                Ok(version) if version != data_version.get() => {
+                    data_version.set(version);
+                    self_clone.list_refresh(); // 重新选中清单时会调用 todo_refresh
+                    self_clone.notify_todos_changed();
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Error checking for database changes: {}", e),
+            }
+            glib::Continue(true)
+        });
     }
 
     fn refresh_marked_day(&self) {
         self.calendar.clear_marks();
 
-        let days = db_get_exists_day(self.db_conn.deref(), self.calendar.year(), self.calendar.month() + 1);
+        let days = self.store().get_exists_day(self.calendar.year(), self.calendar.month() + 1, *self.current_list.deref().borrow());
+        let days = self.check_store(days).unwrap_or_default();
 
         for d in days {
             self.calendar.mark_day(d as u32);
         }
     }
 
+    fn list_refresh(&self) {
+        let current_list = *self.current_list.deref().borrow();
+        self.list_sidebar.foreach(|r| {
+            self.list_sidebar.remove(r);
+        });
+
+        self.list_sidebar.add(&get_list_row_view(None));
+        for list in self.all_list().iter() {
+            self.list_sidebar.add(&get_list_row_view(Some(list)));
+        }
+        self.list_sidebar.show_all();
+
+        // 重新选中原来的清单, 清单被删掉时回到 "All"
+        let rows = self.list_sidebar.children();
+        let row = rows.iter().filter_map(|r| r.downcast_ref::<gtk::ListBoxRow>())
+            .find(|r| get_row_list(r).map(|l| l.id) == current_list)
+            .or_else(|| rows.first().and_then(|r| r.downcast_ref::<gtk::ListBoxRow>()));
+        self.list_sidebar.select_row(row);
+    }
+
+    fn selected_list(&self) -> Option<TodoList> {
+        self.list_sidebar.selected_row().and_then(|r| get_row_list(&r))
+    }
+
+    fn list_add_callback(&self) {
+        if let Some(list) = run_list_edit_dialog(self.window().as_ref(), &gettext("New list"), None) {
+            if let Some(list_id) = self.check_store(self.store().new_list(&list)) {
+                *self.current_list.deref().borrow_mut() = Some(list_id);
+            }
+            self.list_refresh();
+        }
+    }
+
+    fn list_edit_callback(&self) {
+        let list = match self.selected_list() {
+            Some(list) => list,
+            None => return,
+        };
+
+        if let Some(new_list) = run_list_edit_dialog(self.window().as_ref(), &gettext("Edit list"), Some(&list)) {
+            let result = self.store().update_list(&TodoList {
+                id: list.id,
+                name: new_list.name,
+                color: new_list.color,
+            });
+            self.check_store(result);
+            self.list_refresh();
+            self.todo_refresh();
+        }
+    }
+
+    fn list_remove_callback(&self) {
+        let list = match self.selected_list() {
+            Some(list) => list,
+            None => return,
+        };
+
+        // 清单里的 todo 不会被删除, 只是不再属于任何清单
+        if ask_confirm(self.window().as_ref(), &fill_placeholders(&gettext("Delete list \"{}\"? Its todos will be kept."), &[&list.name])) {
+            self.check_store(self.store().del_list(list.id));
+            self.notify_todos_changed();
+            *self.current_list.deref().borrow_mut() = None;
+            self.list_refresh();
+        }
+    }
+
     fn reset_date(&self) {
         *self.current_date.deref().borrow_mut() = None;
     }
 
+    fn select_date(&self, date: Date<Local>) {
+        self.calendar.select_month(date.month() - 1, date.year() as u32);
+        self.calendar.select_day(date.day()); // 会触发 day-selected, 由它负责刷新列表
+    }
+
+    fn clear_date(&self) {
+        self.reset_date();
+        self.reset_date_btn.hide();
+        self.todo_refresh();
+    }
+
+    fn goto_today(&self) {
+        let current_date = self.current_date.deref().borrow().clone();
+        let today_date = Local::now().date();
+        match current_date {
+            Some(current_date) if current_date == today_date => self.clear_date(), // 如果当前选中的已经是今天, 回到没有选择日期的状态
+            _ => self.select_date(today_date),
+        }
+    }
+
+    fn move_date(&self, days: i64) {
+        let current_date = self.current_date.deref().borrow().clone();
+        let date = current_date.unwrap_or(Local::now().date()); // 没有选中日期时从今天开始
+        self.select_date(date + Duration::days(days));
+    }
+
+    fn select_prev_day(&self) {
+        self.move_date(-1);
+    }
+
+    fn select_next_day(&self) {
+        self.move_date(1);
+    }
+
+    fn toggle_search(&self) {
+        let search_mode = !self.search_bar.is_search_mode_enabled();
+        self.search_bar.set_search_mode(search_mode);
+        if search_mode {
+            self.search_entry.grab_focus();
+        }
+    }
+
+    fn window(&self) -> Option<gtk::Window> {
+        self.calendar.toplevel().and_then(|w| w.downcast::<gtk::Window>().ok())
+    }
+
+    fn store(&self) -> Rc<dyn TodoRepository> {
+        self.store.deref().borrow().clone()
+    }
+
+    // 读写数据库出错时提示用户并返回 None, 没有打开窗口时只输出到 stderr
+    fn check_store<T>(&self, result: StoreResult<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                if let Some(window) = self.window().filter(|w| w.is_visible()) {
+                    show_error_dialog(Some(&window), &fill_placeholders(&gettext("Database error: {}"), &[&e.to_string()]));
+                }
+                None
+            }
+        }
+    }
+
+    fn all_list(&self) -> Vec<TodoList> {
+        self.check_store(self.store().all_list()).unwrap_or_default()
+    }
+
+    fn show_shortcuts(&self) {
+        get_shortcuts_window(self.window().as_ref()).show_all();
+    }
+
+    fn import_callback(&self) {
+        let window = self.window();
+        let dialog = gtk::FileChooserNative::new(Some(&gettext("Import todos")), window.as_ref(), gtk::FileChooserAction::Open, Some(&gettext("Import")), Some(&gettext("Cancel")));
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("iCalendar"));
+        filter.add_pattern("*.ics");
+        dialog.add_filter(&filter);
+
+        if dialog.run() != gtk::ResponseType::Accept {
+            return;
+        }
+        let path = match dialog.filename() {
+            Some(path) => path,
+            None => return,
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                let result = import_todos(&content).iter().try_for_each(|imported| {
+                    let todo_id = self.store().new_todo(&imported.todo)?;
+                    self.store().set_alarm(todo_id, &imported.alarms)?;
+                    self.store().set_attachment(todo_id, &imported.attachments)?;
+                    match imported.completed {
+                        true => self.store().set_completed(todo_id, true),
+                        false => Ok(()),
+                    }
+                });
+                self.check_store(result);
+                self.notify_todos_changed();
+                self.todo_refresh();
+            }
+            Err(e) => {
+                show_error_dialog(window.as_ref(), &fill_placeholders(&gettext("Error reading {}: {}"), &[&path.display().to_string(), &e.to_string()]));
+            }
+        }
+    }
+
+    fn export_callback(&self) {
+        let window = self.window();
+        let dialog = gtk::FileChooserNative::new(Some(&gettext("Export todos")), window.as_ref(), gtk::FileChooserAction::Save, Some(&gettext("Export")), Some(&gettext("Cancel")));
+        dialog.set_current_name("todo.ics");
+        dialog.set_do_overwrite_confirmation(true);
+
+        if dialog.run() != gtk::ResponseType::Accept {
+            return;
+        }
+        let path = match dialog.filename() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let result = self.store().all_todo().and_then(|todos| Ok(export_todos(&todos, &self.store().all_alarm()?, &self.store().all_attachment()?)));
+        let content = match self.check_store(result) {
+            Some(content) => content,
+            None => return,
+        };
+        if let Err(e) = std::fs::write(&path, content) {
+            show_error_dialog(window.as_ref(), &fill_placeholders(&gettext("Error writing {}: {}"), &[&path.display().to_string(), &e.to_string()]));
+        }
+    }
+
+    fn preferences_callback(&self) {
+        let window = self.window();
+        let settings = match run_preferences_dialog(window.as_ref(), &self.settings.deref().borrow()) {
+            Some(settings) => settings,
+            None => return,
+        };
+
+        let mut settings = settings;
+        let old_database_path = self.settings.deref().borrow().database_path.clone();
+        if settings.database_path != old_database_path {
+            // 新的数据库打不开时保留原来的路径, 其他设置照常保存
+            match TodoStore::open(settings.database_path.clone()) {
+                Ok(store) => self.switch_store(Rc::new(store)),
+                Err(e) => {
+                    show_error_dialog(window.as_ref(), &fill_placeholders(&gettext("Error opening database: {}"), &[&e.to_string()]));
+                    settings.database_path = old_database_path;
+                }
+            }
+        }
+
+        if let Err(e) = settings.save() {
+            show_error_dialog(window.as_ref(), &fill_placeholders(&gettext("Error writing {}: {}"), &[&Settings::config_path().display().to_string(), &e.to_string()]));
+        }
+        *self.settings.deref().borrow_mut() = settings;
+        self.apply_settings();
+    }
+
+    // 清单的 id 只在原来的数据库中有效, 切换后显示全部清单
+    fn switch_store(&self, store: Rc<dyn TodoRepository>) {
+        *self.store.deref().borrow_mut() = store;
+        *self.current_list.deref().borrow_mut() = None;
+        self.list_refresh();
+        self.notify_todos_changed();
+    }
+
+    // 修改的设置马上生效, 数据库路径在 preferences_callback 中处理
+    fn apply_settings(&self) {
+        let settings = self.settings.deref().borrow().clone();
+        if let Some(window) = self.window() {
+            window.set_window_position(settings.window_position.to_gtk());
+            window.resize(settings.window_width, window.size().1);
+        }
+        self.calendar.set_width_request(settings.calendar_width);
+        self.todo_refresh();
+        self.tray_refresh();
+    }
+
+    fn todo_matches_search(&self, todo: &Todo) -> bool {
+        let keyword = self.search_entry.text().to_lowercase();
+        keyword.is_empty() || todo.content.to_lowercase().contains(&keyword) || todo.notes.to_lowercase().contains(&keyword)
+    }
+
     pub fn build_ui(&self, application: &Application) {
+        let settings = self.settings.deref().borrow().clone();
         let window = gtk::ApplicationWindow::builder().
-            application(application).title("Reminder").border_width(10).
-            window_position(gtk::WindowPosition::Mouse).type_hint(gtk::gdk::WindowTypeHint::Dialog).
-            default_width(600).default_height(-1).build();
+            application(application).title(&gettext("Reminder")).border_width(10).
+            window_position(settings.window_position.to_gtk()).type_hint(gtk::gdk::WindowTypeHint::Dialog).
+            default_width(settings.window_width).default_height(-1).build();
 
+        let header_bar = gtk::HeaderBar::builder().title(&gettext("Reminder")).show_close_button(true).build();
         let main_box = gtk::Box::new(gtk::Orientation::Horizontal, 3);
         let todo_box = gtk::Box::new(gtk::Orientation::Vertical, 0);
-        let panel_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
-        panel_box.set_halign(gtk::Align::Fill);
 
         let todo_msg_list: &ListBox = self.todo_msg_list.borrow();
         todo_msg_list.set_selection_mode(gtk::SelectionMode::Multiple);
         todo_msg_list.set_activate_on_single_click(false);
 
-        let mut icon_list: Vec<&str> = Vec::new();
-        for button in self.todo_edit_panel_button.iter() {
-            icon_list.push(button.0);
-        }
+        let self_clone = self.clone();
+        todo_msg_list.connect_row_activated(move |_, r| { // 双击编辑
+            self_clone.todo_inline_edit_callback(r);
+        });
 
-        let todo_edit_panel = get_icon_view(&icon_list).unwrap();
+        let self_clone = self.clone();
+        self.quick_add_entry.connect_activate(move |_| {
+            self_clone.todo_quick_add_callback();
+        });
+
+        let application_clone = application.clone();
+        todo_msg_list.connect_selected_rows_changed(move |l| {
+            set_selection_actions_enabled(&application_clone, !l.selected_rows().is_empty());
+        });
+
+        let list_sidebar: &ListBox = self.list_sidebar.borrow();
+        let self_clone = self.clone();
+        let application_clone = application.clone();
+        list_sidebar.connect_row_selected(move |_, r| {
+            let list = r.and_then(|r| get_row_list(r));
+            set_list_actions_enabled(&application_clone, list.is_some());
+            *self_clone.current_list.deref().borrow_mut() = list.map(|l| l.id);
+            self_clone.todo_refresh();
+        });
 
-        // glib::clone! break type hinting, just do it myself...
         let self_clone = self.clone();
-        todo_edit_panel.connect_item_activated(move |e, path| {
-            assert_eq!(path.indices().len(), 1);
-            (self_clone.todo_edit_panel_button[path.indices()[0] as usize].1)(&self_clone);
-            e.unselect_all();
+        list_sidebar.connect_row_activated(move |_, r| { // 双击编辑清单
+            if get_row_list(r).is_some() {
+                self_clone.list_edit_callback();
+            }
         });
 
+        let list_scrolled_window = gtk::ScrolledWindow::builder().hscrollbar_policy(gtk::PolicyType::Never).build();
+        list_scrolled_window.add(list_sidebar);
+
+        let list_button_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+        list_button_box.pack_start(&get_action_button("list-add", "app.new-list", &gettext("New list")), false, false, 0);
+        list_button_box.pack_start(&get_action_button("document-page-setup", "app.edit-list", &gettext("Edit selected list")), false, false, 0);
+        list_button_box.pack_start(&get_action_button("list-remove", "app.delete-list", &gettext("Delete selected list")), false, false, 0);
+
+        let list_box = gtk::Box::new(gtk::Orientation::Vertical, 3);
+        list_box.set_width_request(140);
+        list_box.pack_start(&list_scrolled_window, true, true, 0);
+        list_box.pack_start(&list_button_box, false, false, 0);
+
         let calendar: &Calendar = self.calendar.borrow();
-        calendar.set_width_request(250);
+        calendar.set_width_request(settings.calendar_width);
 
         let self_clone = self.clone();
         calendar.connect_day_selected(move |x| {
-            let date = Local.ymd(x.year(), (x.month() + 1) as u32, x.day() as u32);
+            let date = local_date(NaiveDate::from_ymd(x.year(), (x.month() + 1) as u32, x.day() as u32));
             *self_clone.current_date.deref().borrow_mut() = Some(date);
             self_clone.reset_date_btn.set_date(date);
             self_clone.reset_date_btn.show();
@@ -249,54 +1076,188 @@ impl Reminder {
         scrolled_window.add(todo_msg_list);
 
         let reset_date_btn: &ResetDateButton = self.reset_date_btn.borrow();
-        let reset_date_icon_view = &reset_date_btn.reset_date_btn;
-        let reset_date_label = &reset_date_btn.current_date_label;
 
+        let drag_targets = get_todo_drag_targets();
+        let mut list_drag_targets = drag_targets.clone();
+        list_drag_targets.extend(get_uri_drop_targets());
+        todo_msg_list.drag_dest_set(gtk::DestDefaults::ALL, &list_drag_targets, gdk::DragAction::MOVE | gdk::DragAction::COPY);
+        todo_msg_list.connect_drag_motion(|_, c, _, _, time| {
+            if !is_todo_drag(c) {
+                c.drag_status(gdk::DragAction::COPY, time); // 拖进来的文件只能复制, 选了 MOVE 文件管理器会删掉原文件
+            }
+            false
+        });
         let self_clone = self.clone();
-        reset_date_icon_view.connect_item_activated(move |e, _| {
-            self_clone.reset_date();
-            self_clone.reset_date_btn.hide();
-            self_clone.todo_refresh();
-            e.unselect_all();
+        todo_msg_list.connect_drag_data_received(move |_, _, _, y, data, _, _| {
+            match get_dropped_todo_id(data) {
+                Some(todo_id) => self_clone.todo_reorder(todo_id, y),
+                None => self_clone.todo_attach_uris(y, data.uris().iter().map(|uri| uri.to_string()).collect()),
+            }
+        });
+
+        calendar.drag_dest_set(gtk::DestDefaults::ALL, &drag_targets, gdk::DragAction::MOVE);
+        // GtkCalendar 自己会把拖进来的数据当作日期文本处理, 需要拦下它的默认处理
+        calendar.connect_drag_motion(|_, _, _, _, _| { true });
+        calendar.connect_drag_drop(|_, _, _, _, _| { true });
+        let self_clone = self.clone();
+        calendar.connect_drag_data_received(move |c, _, x, y, data, _, _| {
+            c.stop_signal_emission_by_name("drag-data-received");
+            if let (Some(todo_id), Some(date)) = (get_dropped_todo_id(data), get_calendar_date_at(c, x, y)) {
+                self_clone.todo_move_to_date(todo_id, Some(date));
+            }
         });
 
+        reset_date_btn.reset_date_btn.drag_dest_set(gtk::DestDefaults::ALL, &drag_targets, gdk::DragAction::MOVE);
         let self_clone = self.clone();
-        let return_today_btn = get_icon_view(&["go-home"]).unwrap();
-        return_today_btn.connect_item_activated(move |e, _| {
-            let have_current_date = self_clone.current_date.deref().borrow().is_some();
-            if have_current_date {
-                let current_date = self_clone.current_date.deref().borrow().unwrap();
-                let today_date = Local::now().date();
-
-                if current_date == today_date {  // 如果当前选中的已经是今天, 回到没有选择日期的状态
-                    self_clone.reset_date();
-                    self_clone.reset_date_btn.hide();
-                    self_clone.todo_refresh();
-                } else {
-                    self_clone.calendar.set_year(today_date.year());
-                    self_clone.calendar.set_month((today_date.month() - 1) as i32);
-                    self_clone.calendar.set_day(today_date.day() as i32);
+        reset_date_btn.reset_date_btn.connect_drag_data_received(move |_, _, _, _, data, _, _| {
+            if let Some(todo_id) = get_dropped_todo_id(data) {
+                self_clone.todo_move_to_date(todo_id, None); // 拖到这里表示清除日期
+            }
+        });
+
+        let search_entry: &gtk::SearchEntry = self.search_entry.borrow();
+        self.search_bar.connect_entry(search_entry);
+        self.search_bar.add(search_entry);
+
+        let self_clone = self.clone();
+        todo_msg_list.set_filter_func(Some(Box::new(move |r| unsafe {
+            match r.child().unwrap().data::<Todo>("todo") {
+                Some(todo) => self_clone.todo_matches_search(todo.as_ref()),
+                None => true,
+            }
+        })));
+
+        let self_clone = self.clone();
+        search_entry.connect_search_changed(move |_| {
+            self_clone.todo_msg_list.invalidate_filter();
+        });
+
+        for (name, callback) in self.actions.iter() {
+            let action = gio::SimpleAction::new(name, None);
+            let self_clone = self.clone();
+            let callback = *callback;
+            action.connect_activate(move |_, _| { callback(&self_clone) });
+            application.add_action(&action);
+        }
+        set_selection_actions_enabled(application, false);
+
+        let application_clone = application.clone();
+        window.connect_key_press_event(move |w, e| {
+            if let Some(focus) = w.focused_widget() {
+                if focus.is::<gtk::Editable>() || focus.is::<gtk::TextView>() {
+                    return gtk::Inhibit(false); // 正在输入文字, 不处理快捷键
+                }
+                // 先交给有焦点的控件, 例如日历用方向键移动, 列表用回车打开选中的行
+                if w.propagate_key_event(e) {
+                    return gtk::Inhibit(true);
+                }
+                // 按钮的回车和空格由窗口的 activate-focus 处理
+                if focus.is::<gtk::Button>() && is_activate_key(e) {
+                    return gtk::Inhibit(false);
+                }
+            }
+
+            match find_shortcut_action(e) {
+                Some(action) => {
+                    application_clone.activate_action(action, None);
+                    gtk::Inhibit(true)
+                }
+                None => {
+                    gtk::Inhibit(false)
                 }
             }
-            e.unselect_all();
         });
 
-        panel_box.pack_start(&return_today_btn, false, false, 0);
-        panel_box.pack_start(reset_date_icon_view, false, false, 0);
-        panel_box.pack_start(reset_date_label, false, false, 0);
-        panel_box.pack_start(&gtk::Label::new(None), true, true, 0); // padding
-        panel_box.pack_start(&todo_edit_panel, false, false, 0);
+        let primary_menu = gio::Menu::new();
+        primary_menu.append(Some(&gettext("Import...")), Some("app.import"));
+        primary_menu.append(Some(&gettext("Export...")), Some("app.export"));
+        primary_menu.append(Some(&gettext("Preferences")), Some("app.preferences"));
+
+        let primary_menu_btn = gtk::MenuButton::builder().
+            image(&gtk::Image::from_icon_name(Some("open-menu-symbolic"), IconSize::Button)).
+            menu_model(&primary_menu).use_popover(true).tooltip_text(&gettext("Main menu")).build();
 
-        todo_box.pack_start(&panel_box, false, false, 0);
+        header_bar.pack_start(&get_action_button("go-home", "app.today", &gettext("Go to today")));
+        header_bar.pack_start(&reset_date_btn.reset_date_btn);
+        header_bar.pack_start(&reset_date_btn.current_date_label);
+        header_bar.pack_end(&primary_menu_btn);
+        for (icon, action, tooltip) in self.todo_edit_panel_button.iter().rev() { // pack_end 从右往左排列
+            header_bar.pack_end(&get_action_button(icon, action, &gettext(*tooltip)));
+        }
+
+        todo_box.pack_start(self.search_bar.deref(), false, false, 0);
+        todo_box.pack_start(self.quick_add_entry.deref(), false, false, 3);
         todo_box.pack_start(&scrolled_window, true, true, 0);
 
+        main_box.pack_start(&list_box, false, true, 0);
         main_box.pack_start(&todo_box, true, true, 0);
         main_box.pack_start(calendar, false, true, 0);
 
-        self.todo_refresh(); // get todo list
+        self.list_refresh(); // 选中清单时会刷新 todo 列表
+        window.set_titlebar(Some(&header_bar));
         window.add(&main_box);
+        let background = self.background.clone();
+        window.connect_delete_event(move |w, _| {
+            match background.get() {
+                true => w.hide_on_delete(), // 后台模式只隐藏窗口, 下次打开时复用
+                false => gtk::Inhibit(false),
+            }
+        });
         window.show_all();
 
         reset_date_btn.hide(); // hide reset btn in default
     }
+}
+
+fn set_list_actions_enabled(application: &Application, enabled: bool) {
+    for name in ["edit-list", "delete-list"] {
+        if let Some(action) = application.lookup_action(name) {
+            action.downcast::<gio::SimpleAction>().unwrap().set_enabled(enabled);
+        }
+    }
+}
+
+fn set_selection_actions_enabled(application: &Application, enabled: bool) {
+    for name in ["edit", "complete", "delete", "move-up", "move-down"] {
+        if let Some(action) = application.lookup_action(name) {
+            action.downcast::<gio::SimpleAction>().unwrap().set_enabled(enabled);
+        }
+    }
+}
+
+fn is_activate_key(event: &gdk::EventKey) -> bool {
+    use gdk::keys::constants::{ISO_Enter, KP_Enter, KP_Space, Return, space};
+    let keyval = event.keyval();
+    return [Return, KP_Enter, ISO_Enter, space, KP_Space].contains(&keyval);
+}
+
+// 按设置加上默认提醒, 命令行, D-Bus 和快速添加都使用这个函数
+pub fn add_todo_from_text(store: &dyn TodoRepository, settings: &Settings, text: String, date: Option<NaiveDate>, list_id: Option<i32>) -> StoreResult<i32> {
+    let todo = new_todo_from_text(text, date, list_id, settings);
+    let todo_id = store.new_todo(&todo)?;
+    if todo.expire_time.is_some() {
+        store.set_alarm(todo_id, &settings.default_alarms())?;
+    }
+    return Ok(todo_id);
+}
+
+// 解析自然语言的 todo, 没写日期时使用 date 作为日期, 没写时间时为全天 todo
+pub fn new_todo_from_text(text: String, date: Option<NaiveDate>, list_id: Option<i32>, settings: &Settings) -> NewTodo {
+    let parsed = parse_todo_on(&text, Local::now().naive_local(), date, get_week_start(), settings.default_time);
+    return NewTodo {
+        content: if parsed.content.is_empty() { text } else { parsed.content.clone() },
+        expire_time: match (parsed.expire_time(), date) {
+            (Some(_), _) if parsed.is_all_day() => parsed.date.map(|date| date.and_hms(0, 0, 0)),
+            (Some(time), _) => Some(wall_to_utc(&time, None)),
+            (None, Some(date)) => Some(date.and_hms(0, 0, 0)),
+            (None, None) => None,
+        },
+        timezone: None,
+        all_day: parsed.is_all_day() || (parsed.expire_time().is_none() && date.is_some()),
+        start_time: None,
+        hide_until_start: false,
+        notes: String::new(),
+        list_id: list_id,
+        priority: PRIORITY_NONE,
+    };
 }
\ No newline at end of file
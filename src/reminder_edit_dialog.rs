@@ -1,24 +1,102 @@
 use std::borrow::Borrow;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::ops::Deref;
 use std::rc::Rc;
 
-use chrono::{Datelike, DateTime, Local, Timelike, TimeZone};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike, TimeZone};
+use gettextrs::{gettext, ngettext};
 use gtk::prelude::*;
 use gtk::Widget;
 
-use crate::utils::get_days_from_month;
+use reminder_core::{NewAlarm, NewAttachment, parse_timezone, PRIORITY_HIGH, PRIORITY_LOW, PRIORITY_MEDIUM, PRIORITY_NONE, timezone_names, Todo, TodoList, wall_to_utc};
 
+use crate::date_parser::parse_todo;
+use crate::i18n::fill_placeholders;
+use crate::settings::Settings;
+use crate::utils::{format_date_time, get_attachment_name, get_days_from_month, get_week_start, open_uri};
+
+// 编辑框中的时间, time 为 timezone 中的墙上时间, 全天时只用到日期
 #[derive(Clone)]
-pub struct Timepicker {
+pub struct TodoTime {
+    pub time: NaiveDateTime,
+    pub timezone: Option<String>,
+    pub all_day: bool,
+}
+
+impl TodoTime {
+    pub fn from_todo(todo: &Todo) -> Option<TodoTime> {
+        Some(TodoTime {
+            time: todo.wall_expire_time()?,
+            timezone: todo.timezone.clone(),
+            all_day: todo.all_day,
+        })
+    }
+
+    // 存到数据库中的 expire_time
+    pub fn expire_time(&self) -> NaiveDateTime {
+        match self.all_day {
+            true => self.time.date().and_hms(0, 0, 0),
+            false => wall_to_utc(&self.time, self.timezone.as_deref()),
+        }
+    }
+
+    pub fn local_date(&self) -> NaiveDate {
+        match self.all_day {
+            true => self.time.date(),
+            false => Local.from_utc_datetime(&self.expire_time()).date().naive_local(),
+        }
+    }
+}
+
+// 开始时间, time 和 TodoTime 一样是墙上时间
+#[derive(Clone)]
+pub struct TodoStart {
+    pub time: NaiveDateTime,
+    pub hide_until_start: bool,
+}
+
+impl TodoStart {
+    pub fn from_todo(todo: &Todo) -> Option<TodoStart> {
+        Some(TodoStart {
+            time: todo.wall_start_time()?,
+            hide_until_start: todo.hide_until_start,
+        })
+    }
+
+    // 存到数据库中的 start_time, 使用到期时间的时区, 晚于到期时间时按到期时间算
+    pub fn start_time(&self, time: Option<&TodoTime>) -> NaiveDateTime {
+        match time {
+            Some(time) if time.all_day => self.time.date().and_hms(0, 0, 0).min(time.expire_time()),
+            Some(time) => wall_to_utc(&self.time, time.timezone.as_deref()).min(time.expire_time()),
+            None => wall_to_utc(&self.time, None),
+        }
+    }
+}
+
+// 编辑框中填写的所有内容, 保存时一起交给 connect_hide 的回调
+pub struct TodoEdit {
+    pub content: String,
+    pub notes: String,
+    pub time: Option<TodoTime>,
+    pub start: Option<TodoStart>,
+    pub alarms: Vec<NewAlarm>,
+    pub attachments: Vec<NewAttachment>,
+    pub list_id: Option<i32>,
+    pub priority: i32,
+}
+
+// 年月日时分的选择框, 到期时间和开始时间共用
+#[derive(Clone)]
+pub struct DateTimeSpin {
     year_picker: gtk::SpinButton,
     month_picker: gtk::SpinButton,
     day_picker: gtk::SpinButton,
     hour_picker: gtk::SpinButton,
     minute_picker: gtk::SpinButton,
+    time_box: gtk::Box,
 }
 
-impl Timepicker {
+impl DateTimeSpin {
     fn new() -> Self {
         let todo_year_picker = gtk::SpinButton::builder().adjustment(
             &gtk::Adjustment::builder().upper(2077f64).lower(1970f64).step_increment(1f64).build()
@@ -37,16 +115,17 @@ impl Timepicker {
             &gtk::Adjustment::builder().upper(59f64).lower(0f64).step_increment(1f64).build()
         ).orientation(gtk::Orientation::Vertical).build();
 
-        Timepicker {
+        DateTimeSpin {
             year_picker: todo_year_picker,
             month_picker: todo_month_picker,
             day_picker: todo_day_picker,
             hour_picker: todo_hour_picker,
             minute_picker: todo_minute_picker,
+            time_box: gtk::Box::builder().orientation(gtk::Orientation::Horizontal).spacing(3).margin_start(6).build(),
         }
     }
 
-    fn build_ui(&self) -> impl IsA<Widget> {
+    fn build_ui(&self) -> gtk::Box {
         let todo_timepicker = gtk::Box::builder().orientation(gtk::Orientation::Horizontal).
             halign(gtk::Align::Center).spacing(3).margin_start(3).margin_bottom(6).build();
 
@@ -70,32 +149,24 @@ impl Timepicker {
             return gtk::Inhibit(false);
         });
 
-        todo_date_timepicker.attach(&gtk::Label::builder().label("Year").margin_end(6).halign(gtk::Align::End).build(), 0, 0, 1, 1);
+        todo_date_timepicker.attach(&gtk::Label::builder().label(&gettext("Year")).margin_end(6).halign(gtk::Align::End).build(), 0, 0, 1, 1);
         todo_date_timepicker.attach(&self.year_picker, 1, 0, 1, 1);
-        todo_date_timepicker.attach(&gtk::Label::builder().label("Month").margin_end(6).halign(gtk::Align::End).build(), 0, 1, 1, 1);
+        todo_date_timepicker.attach(&gtk::Label::builder().label(&gettext("Month")).margin_end(6).halign(gtk::Align::End).build(), 0, 1, 1, 1);
         todo_date_timepicker.attach(&self.month_picker, 1, 1, 1, 1);
-        todo_date_timepicker.attach(&gtk::Label::builder().label("Day").margin_end(6).halign(gtk::Align::End).build(), 0, 2, 1, 1);
+        todo_date_timepicker.attach(&gtk::Label::builder().label(&gettext("Day")).margin_end(6).halign(gtk::Align::End).build(), 0, 2, 1, 1);
         todo_date_timepicker.attach(&self.day_picker, 1, 2, 1, 1);
 
-        let todo_time_timepicker = gtk::Box::builder().orientation(gtk::Orientation::Horizontal).spacing(3).margin_start(6).build();
-
-        todo_time_timepicker.pack_start(&self.hour_picker, false, false, 0);
-        todo_time_timepicker.pack_start(&gtk::Label::builder().label(":").build(), false, false, 0);
-        todo_time_timepicker.pack_start(&self.minute_picker, false, false, 0);
+        self.time_box.pack_start(&self.hour_picker, false, false, 0);
+        self.time_box.pack_start(&gtk::Label::builder().label(":").build(), false, false, 0);
+        self.time_box.pack_start(&self.minute_picker, false, false, 0);
 
         todo_timepicker.pack_start(&todo_date_timepicker, true, true, 0);
-        todo_timepicker.pack_start(&todo_time_timepicker, true, true, 0);
+        todo_timepicker.pack_start(&self.time_box, true, true, 0);
 
-        let todo_timepicker_with_label = gtk::Box::builder().orientation(gtk::Orientation::Vertical).
-            halign(gtk::Align::Fill).build();
-        let label = gtk::Label::builder().label("<b>DateTime:</b>").use_markup(true).margin_start(3).halign(gtk::Align::Start).build();
-        todo_timepicker_with_label.pack_start(&label, false, false, 0);
-        todo_timepicker_with_label.pack_start(&todo_timepicker, false, false, 0);
-
-        return todo_timepicker_with_label;
+        return todo_timepicker;
     }
 
-    fn set_time(&self, time: DateTime<Local>) {
+    fn set_time(&self, time: NaiveDateTime) {
         self.year_picker.set_value(time.year() as f64);
         self.month_picker.set_value(time.month() as f64);
         self.day_picker.set_value(time.day() as f64);
@@ -103,17 +174,493 @@ impl Timepicker {
         self.minute_picker.set_value(time.minute() as f64);
     }
 
-    fn get_time(&self) -> DateTime<Local> {
-        Local.ymd(self.year_picker.value() as i32, self.month_picker.value() as u32, self.day_picker.value() as u32)
+    fn get_time(&self) -> NaiveDateTime {
+        NaiveDate::from_ymd(self.year_picker.value() as i32, self.month_picker.value() as u32, self.day_picker.value() as u32)
             .and_hms(self.hour_picker.value() as u32, self.minute_picker.value() as u32, 0u32)
     }
+
+    // 全天时隐藏时分
+    fn set_all_day(&self, all_day: bool) {
+        self.time_box.set_visible(!all_day);
+    }
+
+    // 超出范围的年份会被截断
+    fn set_year_range(&self, min_year: i32, max_year: i32) {
+        self.year_picker.set_range(min_year as f64, max_year as f64);
+    }
+}
+
+#[derive(Clone)]
+pub struct Timepicker {
+    spin: DateTimeSpin,
+    natural_time_entry: gtk::Entry,
+    timezone_entry: gtk::Entry,
+    all_day_check: gtk::CheckButton,
+    default_time: Rc<Cell<NaiveTime>>,
+}
+
+impl Timepicker {
+    fn new() -> Self {
+        Timepicker {
+            spin: DateTimeSpin::new(),
+            natural_time_entry: gtk::Entry::builder().placeholder_text(&gettext("e.g. tomorrow 3pm, 下周一上午十点")).
+                margin_start(3).margin_end(3).margin_bottom(3).build(),
+            timezone_entry: gtk::Entry::builder().placeholder_text(&gettext("Local")).
+                tooltip_text(&gettext("Time zone of this todo, e.g. Asia/Tokyo, leave empty to follow the system")).
+                margin_start(3).margin_end(3).margin_bottom(6).build(),
+            all_day_check: gtk::CheckButton::builder().label(&gettext("All day")).margin_start(3).build(),
+            default_time: Rc::new(Cell::new(NaiveTime::from_hms(9, 0, 0))),
+        }
+    }
+
+    fn build_ui(&self) -> impl IsA<Widget> {
+        let self_clone = self.clone();
+        self.natural_time_entry.connect_changed(move |e| {
+            let parsed = parse_todo(&e.text(), Local::now().naive_local(), get_week_start(), self_clone.default_time.get());
+            if let Some(time) = parsed.expire_time() {
+                self_clone.spin.set_time(time);
+                self_clone.all_day_check.set_active(parsed.is_all_day());
+            }
+        });
+
+        let self_clone = self.clone();
+        self.all_day_check.connect_toggled(move |_| {
+            self_clone.update_all_day();
+        });
+
+        let timezone_store = gtk::ListStore::new(&[String::static_type()]);
+        for name in timezone_names() {
+            timezone_store.set(&timezone_store.append(), &[(0, &name)]);
+        }
+        let timezone_completion = gtk::EntryCompletion::builder().model(&timezone_store).text_column(0).minimum_key_length(2).build();
+        timezone_completion.set_match_func(|c, key, iter| {
+            let name = c.model().unwrap().value(iter, 0).get::<String>().unwrap();
+            return name.to_lowercase().contains(key); // key 已经转为小写
+        });
+        self.timezone_entry.set_completion(Some(&timezone_completion));
+
+        self.timezone_entry.connect_changed(|e| {
+            let text = e.text();
+            if text.trim().is_empty() || parse_timezone(text.trim()).is_some() {
+                e.style_context().remove_class("error");
+            } else {
+                e.style_context().add_class("error");
+            }
+        });
+
+        let todo_timepicker_with_label = gtk::Box::builder().orientation(gtk::Orientation::Vertical).
+            halign(gtk::Align::Fill).build();
+        let label = gtk::Label::builder().label(&format!("<b>{}</b>", gettext("DateTime:"))).use_markup(true).margin_start(3).halign(gtk::Align::Start).build();
+        todo_timepicker_with_label.pack_start(&label, false, false, 0);
+        todo_timepicker_with_label.pack_start(&self.natural_time_entry, false, false, 0);
+        todo_timepicker_with_label.pack_start(&self.all_day_check, false, false, 0);
+        todo_timepicker_with_label.pack_start(&self.spin.build_ui(), false, false, 0);
+        todo_timepicker_with_label.pack_start(&gtk::Label::builder().label(&format!("<b>{}</b>", gettext("Time zone:"))).use_markup(true).margin_start(3).halign(gtk::Align::Start).build(), false, false, 0);
+        todo_timepicker_with_label.pack_start(&self.timezone_entry, false, false, 0);
+
+        return todo_timepicker_with_label;
+    }
+
+    fn set_timezone(&self, timezone: Option<String>) {
+        self.timezone_entry.set_text(&timezone.unwrap_or_default());
+    }
+
+    // 为空时使用本地时区, 无法识别时不能保存, 见 is_valid
+    fn get_timezone(&self) -> Option<String> {
+        let text = self.timezone_entry.text();
+        parse_timezone(text.trim()).map(|tz| String::from(tz.name()))
+    }
+
+    // 全天的 todo 不使用时区, 所以不检查
+    fn is_valid(&self) -> bool {
+        let text = self.timezone_entry.text();
+        self.all_day_check.is_active() || text.trim().is_empty() || parse_timezone(text.trim()).is_some()
+    }
+
+    // 时区或全天改变后 is_valid 的结果可能变化
+    fn connect_validity_changed<F: Fn() + Clone + 'static>(&self, f: F) {
+        let f_clone = f.clone();
+        self.timezone_entry.connect_changed(move |_| f_clone());
+        self.all_day_check.connect_toggled(move |_| f());
+    }
+
+    // 全天时隐藏时分, 时区也没有意义
+    fn update_all_day(&self) {
+        let all_day = self.all_day_check.is_active();
+        self.spin.set_all_day(all_day);
+        self.timezone_entry.set_sensitive(!all_day);
+    }
+
+    fn set_todo_time(&self, time: TodoTime) {
+        self.spin.set_time(time.time);
+        self.set_timezone(time.timezone);
+        self.all_day_check.set_active(time.all_day);
+    }
+
+    fn get_todo_time(&self) -> TodoTime {
+        let all_day = self.all_day_check.is_active();
+        TodoTime {
+            time: self.spin.get_time(),
+            timezone: if all_day { None } else { self.get_timezone() },
+            all_day: all_day,
+        }
+    }
+}
+
+// 开始时间, 和到期时间使用相同的时区, 没有到期时间时为本地时间
+#[derive(Clone)]
+pub struct StartPicker {
+    spin: DateTimeSpin,
+    start_check: gtk::CheckButton,
+    hide_until_start_check: gtk::CheckButton,
+}
+
+impl StartPicker {
+    fn new() -> Self {
+        let spin = DateTimeSpin::new();
+        spin.set_time(Local::now().naive_local());
+        StartPicker {
+            spin: spin,
+            start_check: gtk::CheckButton::builder().label(&format!("<b>{}</b>", gettext("Start:"))).margin_start(3).build(),
+            hide_until_start_check: gtk::CheckButton::builder().label(&gettext("Hide until start")).
+                tooltip_text(&gettext("Keep this todo out of the list without date until it starts")).margin_start(3).build(),
+        }
+    }
+
+    fn build_ui(&self) -> impl IsA<Widget> {
+        if let Some(label) = self.start_check.child().and_then(|c| c.downcast::<gtk::Label>().ok()) {
+            label.set_use_markup(true);
+        }
+
+        let spin = self.spin.build_ui();
+        let spin_clone = spin.clone();
+        let hide_until_start_check = self.hide_until_start_check.clone();
+        self.start_check.connect_toggled(move |c| {
+            spin_clone.set_sensitive(c.is_active());
+            hide_until_start_check.set_sensitive(c.is_active());
+        });
+        spin.set_sensitive(false);
+        self.hide_until_start_check.set_sensitive(false);
+
+        let start_picker = gtk::Box::builder().orientation(gtk::Orientation::Vertical).halign(gtk::Align::Fill).build();
+        start_picker.pack_start(&self.start_check, false, false, 0);
+        start_picker.pack_start(&spin, false, false, 0);
+        start_picker.pack_start(&self.hide_until_start_check, false, false, 0);
+        return start_picker;
+    }
+
+    fn set_start(&self, start: Option<TodoStart>) {
+        match start {
+            Some(start) => {
+                self.spin.set_time(start.time);
+                self.hide_until_start_check.set_active(start.hide_until_start);
+                self.start_check.set_active(true);
+            }
+            None => {
+                self.start_check.set_active(false);
+            }
+        }
+    }
+
+    fn get_start(&self) -> Option<TodoStart> {
+        if !self.start_check.is_active() {
+            return None;
+        }
+        Some(TodoStart {
+            time: self.spin.get_time(),
+            hide_until_start: self.hide_until_start_check.is_active(),
+        })
+    }
+}
+
+// 例如 "1 day 2 hours", minutes 不能为负数
+fn format_minutes(minutes: i32) -> String {
+    let days = minutes / (24 * 60);
+    let hours = minutes / 60 % 24;
+    let minutes = minutes % 60;
+    let mut text = Vec::new();
+    if days > 0 {
+        text.push(fill_placeholders(&ngettext("{} day", "{} days", days as u32), &[&days.to_string()]));
+    }
+    if hours > 0 {
+        text.push(fill_placeholders(&ngettext("{} hour", "{} hours", hours as u32), &[&hours.to_string()]));
+    }
+    if minutes > 0 {
+        text.push(fill_placeholders(&ngettext("{} minute", "{} minutes", minutes as u32), &[&minutes.to_string()]));
+    }
+    return text.join(" ");
+}
+
+fn get_alarm_text(alarm: &NewAlarm) -> String {
+    match (alarm.trigger_time, alarm.trigger_offset) {
+        // Translators: 某个时刻的提醒, 格式见 g_date_time_format
+        (Some(time), _) => fill_placeholders(&gettext("At {}"), &[&format_date_time(&Local.from_utc_datetime(&time).naive_local(), &gettext("%Y-%m-%d %H:%M"))]),
+        (None, Some(0)) => gettext("At due time"),
+        (None, Some(offset)) if offset > 0 => fill_placeholders(&gettext("{} before"), &[&format_minutes(offset)]),
+        (None, Some(offset)) => fill_placeholders(&gettext("{} after"), &[&format_minutes(-offset)]),
+        (None, None) => String::new(),
+    }
+}
+
+// 提醒列表, 可以添加相对到期时间的提醒或者某个时刻的提醒, 绝对时间为本地时间
+#[derive(Clone)]
+pub struct AlarmPicker {
+    alarms: Rc<RefCell<Vec<NewAlarm>>>,
+    alarm_list: gtk::ListBox,
+    kind_combo: gtk::ComboBoxText,
+    amount_spin: gtk::SpinButton,
+    unit_combo: gtk::ComboBoxText,
+    offset_box: gtk::Box,
+    spin: DateTimeSpin,
+    at_box: gtk::Box,
+}
+
+impl AlarmPicker {
+    fn new(have_due: bool) -> Self {
+        let kind_combo = gtk::ComboBoxText::new();
+        kind_combo.append(Some("before"), &gettext("Before due"));
+        kind_combo.append(Some("at"), &gettext("At time"));
+        kind_combo.set_active_id(Some(if have_due { "before" } else { "at" }));
+        kind_combo.set_sensitive(have_due); // 没有到期时间时只能设置绝对时间
+
+        let unit_combo = gtk::ComboBoxText::new();
+        unit_combo.append(Some("1"), &gettext("minutes"));
+        unit_combo.append(Some("60"), &gettext("hours"));
+        unit_combo.append(Some("1440"), &gettext("days"));
+        unit_combo.set_active_id(Some("1"));
+
+        let spin = DateTimeSpin::new();
+        spin.set_time(Local::now().naive_local());
+
+        AlarmPicker {
+            alarms: Rc::new(RefCell::new(Vec::new())),
+            alarm_list: gtk::ListBox::builder().selection_mode(gtk::SelectionMode::None).margin_start(3).margin_end(3).build(),
+            kind_combo: kind_combo,
+            amount_spin: gtk::SpinButton::builder().adjustment(
+                &gtk::Adjustment::builder().upper(9999f64).lower(0f64).step_increment(1f64).value(15f64).build()
+            ).build(),
+            unit_combo: unit_combo,
+            offset_box: gtk::Box::builder().orientation(gtk::Orientation::Horizontal).spacing(3).build(),
+            spin: spin,
+            at_box: gtk::Box::builder().orientation(gtk::Orientation::Vertical).build(),
+        }
+    }
+
+    fn build_ui(&self) -> impl IsA<Widget> {
+        let self_clone = self.clone();
+        self.kind_combo.connect_changed(move |_| {
+            self_clone.update_kind();
+        });
+
+        let add_button = gtk::Button::builder().label(&gettext("Add")).build();
+        let self_clone = self.clone();
+        add_button.connect_clicked(move |_| {
+            let alarm = self_clone.get_input_alarm();
+            if !self_clone.alarms.deref().borrow().contains(&alarm) {
+                self_clone.alarms.deref().borrow_mut().push(alarm);
+            }
+            self_clone.refresh_list();
+        });
+
+        self.offset_box.pack_start(&self.amount_spin, false, false, 0);
+        self.offset_box.pack_start(&self.unit_combo, false, false, 0);
+        self.at_box.pack_start(&self.spin.build_ui(), false, false, 0);
+
+        let input_box = gtk::Box::builder().orientation(gtk::Orientation::Horizontal).spacing(3).margin_start(3).margin_end(3).margin_top(3).build();
+        input_box.pack_start(&self.kind_combo, false, false, 0);
+        input_box.pack_start(&self.offset_box, false, false, 0);
+        input_box.pack_end(&add_button, false, false, 0);
+
+        let alarm_picker = gtk::Box::builder().orientation(gtk::Orientation::Vertical).halign(gtk::Align::Fill).margin_bottom(6).build();
+        alarm_picker.pack_start(&gtk::Label::builder().label(&format!("<b>{}</b>", gettext("Alarms:"))).use_markup(true).margin_start(3).halign(gtk::Align::Start).build(), false, false, 0);
+        alarm_picker.pack_start(&self.alarm_list, false, false, 0);
+        alarm_picker.pack_start(&input_box, false, false, 0);
+        alarm_picker.pack_start(&self.at_box, false, false, 0);
+        return alarm_picker;
+    }
+
+    fn update_kind(&self) {
+        let at_time = self.kind_combo.active_id().as_deref() == Some("at");
+        self.offset_box.set_visible(!at_time);
+        self.at_box.set_visible(at_time);
+    }
+
+    fn get_input_alarm(&self) -> NewAlarm {
+        match self.kind_combo.active_id().as_deref() {
+            Some("at") => NewAlarm {
+                trigger_time: Some(wall_to_utc(&self.spin.get_time(), None)),
+                trigger_offset: None,
+            },
+            _ => {
+                let unit = self.unit_combo.active_id().and_then(|id| id.parse::<i32>().ok()).unwrap_or(1);
+                NewAlarm {
+                    trigger_time: None,
+                    trigger_offset: Some(self.amount_spin.value() as i32 * unit),
+                }
+            }
+        }
+    }
+
+    fn refresh_list(&self) {
+        self.alarm_list.foreach(|r| {
+            self.alarm_list.remove(r);
+        });
+
+        for (index, alarm) in self.alarms.deref().borrow().iter().enumerate() {
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, 3);
+            row.pack_start(&gtk::Label::builder().label(&get_alarm_text(alarm)).halign(gtk::Align::Start).build(), true, true, 0);
+
+            let remove_button = gtk::Button::builder().
+                image(&gtk::Image::from_icon_name(Some("list-remove"), gtk::IconSize::Button)).
+                relief(gtk::ReliefStyle::None).tooltip_text(&gettext("Remove alarm")).build();
+            let self_clone = self.clone();
+            remove_button.connect_clicked(move |_| {
+                self_clone.alarms.deref().borrow_mut().remove(index);
+                self_clone.refresh_list();
+            });
+            row.pack_end(&remove_button, false, false, 0);
+            self.alarm_list.add(&row);
+        }
+        self.alarm_list.show_all();
+    }
+
+    fn set_alarms(&self, alarms: Vec<NewAlarm>) {
+        *self.alarms.deref().borrow_mut() = alarms;
+        self.refresh_list();
+    }
+
+    fn get_alarms(&self) -> Vec<NewAlarm> {
+        self.alarms.deref().borrow().clone()
+    }
+}
+
+// 附件列表, 可以选择本地文件或者填写链接
+#[derive(Clone)]
+pub struct AttachmentPicker {
+    attachments: Rc<RefCell<Vec<NewAttachment>>>,
+    attachment_list: gtk::ListBox,
+    url_entry: gtk::Entry,
+}
+
+impl AttachmentPicker {
+    fn new() -> Self {
+        AttachmentPicker {
+            attachments: Rc::new(RefCell::new(Vec::new())),
+            attachment_list: gtk::ListBox::builder().selection_mode(gtk::SelectionMode::None).margin_start(3).margin_end(3).build(),
+            url_entry: gtk::Entry::builder().placeholder_text("https://...").build(),
+        }
+    }
+
+    fn build_ui(&self) -> impl IsA<Widget> {
+        let add_url_button = gtk::Button::builder().label(&gettext("Add link")).build();
+        let self_clone = self.clone();
+        add_url_button.connect_clicked(move |_| {
+            let url = self_clone.url_entry.text().trim().to_string();
+            if url.is_empty() {
+                return;
+            }
+            let uri = if url.contains("://") || url.starts_with("mailto:") { url } else { format!("https://{}", url) };
+            self_clone.add_uri(uri);
+            self_clone.url_entry.set_text("");
+        });
+        let add_url_button_clone = add_url_button.clone();
+        self.url_entry.connect_activate(move |_| {
+            add_url_button_clone.clicked();
+        });
+
+        let add_file_button = gtk::Button::builder().label(&gettext("Add file...")).build();
+        let self_clone = self.clone();
+        add_file_button.connect_clicked(move |_| {
+            let window = self_clone.window();
+            let dialog = gtk::FileChooserNative::new(Some(&gettext("Attach files")), window.as_ref(), gtk::FileChooserAction::Open, Some(&gettext("Attach")), Some(&gettext("Cancel")));
+            dialog.set_select_multiple(true);
+            if dialog.run() == gtk::ResponseType::Accept {
+                for uri in dialog.uris() {
+                    self_clone.add_uri(uri.to_string());
+                }
+            }
+        });
+
+        let input_box = gtk::Box::builder().orientation(gtk::Orientation::Horizontal).spacing(3).margin_start(3).margin_end(3).margin_top(3).build();
+        input_box.pack_start(&self.url_entry, true, true, 0);
+        input_box.pack_start(&add_url_button, false, false, 0);
+        input_box.pack_start(&add_file_button, false, false, 0);
+
+        let attachment_picker = gtk::Box::builder().orientation(gtk::Orientation::Vertical).halign(gtk::Align::Fill).margin_bottom(6).build();
+        attachment_picker.pack_start(&gtk::Label::builder().label(&format!("<b>{}</b>", gettext("Attachments:"))).use_markup(true).margin_start(3).halign(gtk::Align::Start).build(), false, false, 0);
+        attachment_picker.pack_start(&self.attachment_list, false, false, 0);
+        attachment_picker.pack_start(&input_box, false, false, 0);
+        return attachment_picker;
+    }
+
+    fn window(&self) -> Option<gtk::Window> {
+        self.attachment_list.toplevel().and_then(|w| w.downcast::<gtk::Window>().ok())
+    }
+
+    fn add_uri(&self, uri: String) {
+        let attachment = NewAttachment { uri: uri };
+        if !self.attachments.deref().borrow().contains(&attachment) {
+            self.attachments.deref().borrow_mut().push(attachment);
+        }
+        self.refresh_list();
+    }
+
+    fn refresh_list(&self) {
+        self.attachment_list.foreach(|r| {
+            self.attachment_list.remove(r);
+        });
+
+        for (index, attachment) in self.attachments.deref().borrow().iter().enumerate() {
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, 3);
+            row.pack_start(&gtk::Label::builder().label(&get_attachment_name(&attachment.uri)).tooltip_text(&attachment.uri).
+                ellipsize(gtk::pango::EllipsizeMode::Middle).halign(gtk::Align::Start).build(), true, true, 0);
+
+            let remove_button = gtk::Button::builder().
+                image(&gtk::Image::from_icon_name(Some("list-remove"), gtk::IconSize::Button)).
+                relief(gtk::ReliefStyle::None).tooltip_text(&gettext("Remove attachment")).build();
+            let self_clone = self.clone();
+            remove_button.connect_clicked(move |_| {
+                self_clone.attachments.deref().borrow_mut().remove(index);
+                self_clone.refresh_list();
+            });
+            row.pack_end(&remove_button, false, false, 0);
+
+            let open_button = gtk::Button::builder().
+                image(&gtk::Image::from_icon_name(Some("document-open"), gtk::IconSize::Button)).
+                relief(gtk::ReliefStyle::None).tooltip_text(&gettext("Open")).build();
+            let self_clone = self.clone();
+            let uri = attachment.uri.clone();
+            open_button.connect_clicked(move |_| {
+                open_uri(self_clone.window().as_ref(), &uri);
+            });
+            row.pack_end(&open_button, false, false, 0);
+
+            self.attachment_list.add(&row);
+        }
+        self.attachment_list.show_all();
+    }
+
+    fn set_attachments(&self, attachments: Vec<NewAttachment>) {
+        *self.attachments.deref().borrow_mut() = attachments;
+        self.refresh_list();
+    }
+
+    fn get_attachments(&self) -> Vec<NewAttachment> {
+        self.attachments.deref().borrow().clone()
+    }
 }
 
 #[derive(Clone)]
 pub struct ReminderEditDialog {
     dialog: Rc<gtk::Dialog>,
-    todo_content_view: Rc<gtk::TextView>,
+    todo_title_entry: Rc<gtk::Entry>,
+    list_combo: Rc<gtk::ComboBoxText>,
+    priority_combo: Rc<gtk::ComboBoxText>,
+    todo_notes_view: Rc<gtk::TextView>,
     todo_timepicker: Rc<Option<Timepicker>>,
+    start_picker: Rc<StartPicker>,
+    alarm_picker: Rc<AlarmPicker>,
+    attachment_picker: Rc<AttachmentPicker>,
     save_todo: Rc<RefCell<bool>>,
 }
 
@@ -123,37 +670,82 @@ impl ReminderEditDialog {
             .type_hint(gtk::gdk::WindowTypeHint::Dialog)
             .width_request(400).title(title).destroy_with_parent(true).build();
 
-        let content_label = gtk::Label::builder().margin_start(3).label("<b>Content:</b>").use_markup(true).halign(gtk::Align::Start).build();
+        let title_label = gtk::Label::builder().margin_start(3).label(&format!("<b>{}</b>", gettext("Title:"))).use_markup(true).halign(gtk::Align::Start).build();
+        let todo_title_entry = gtk::Entry::builder().margin_start(3).margin_end(3).margin_bottom(3).build();
 
-        let todo_content_window = gtk::ScrolledWindow::builder().border_width(3).build();
-        let todo_content_frame = gtk::Frame::builder().border_width(3).build();
-        let todo_content_view = gtk::TextView::builder().wrap_mode(gtk::WrapMode::WordChar).build();
-        todo_content_window.add(&todo_content_view);
-        todo_content_frame.add(&todo_content_window);
+        let notes_label = gtk::Label::builder().margin_start(3).label(&format!("<b>{}</b>", gettext("Notes:"))).use_markup(true).halign(gtk::Align::Start).build();
+        let todo_notes_window = gtk::ScrolledWindow::builder().border_width(3).build();
+        let todo_notes_frame = gtk::Frame::builder().border_width(3).build();
+        let todo_notes_view = gtk::TextView::builder().wrap_mode(gtk::WrapMode::WordChar).build();
+        todo_notes_window.add(&todo_notes_view);
+        todo_notes_frame.add(&todo_notes_window);
 
         let button_box = gtk::Box::builder().orientation(gtk::Orientation::Horizontal).halign(gtk::Align::Center).margin_bottom(6).margin_top(3).build();
-        let cancel_button = gtk::Button::builder().label("Cancel").margin_end(3).build();
-        let save_button = gtk::Button::builder().label("Save").margin_start(3).build();
+        let cancel_button = gtk::Button::builder().label(&gettext("Cancel")).margin_end(3).build();
+        let save_button = gtk::Button::builder().label(&gettext("Save")).margin_start(3).build();
 
         button_box.pack_start(&cancel_button, false, false, 0);
         button_box.pack_start(&save_button, false, false, 0);
 
-        dialog.content_area().pack_start(&content_label, false, false, 0);
-        dialog.content_area().pack_start(&todo_content_frame, true, true, 0);
-
+        let list_box = gtk::Box::builder().orientation(gtk::Orientation::Horizontal).spacing(6).margin_start(3).margin_end(3).margin_bottom(3).build();
+        let list_combo = gtk::ComboBoxText::new();
+        list_box.pack_start(&gtk::Label::builder().label(&format!("<b>{}</b>", gettext("List:"))).use_markup(true).build(), false, false, 0);
+        list_box.pack_start(&list_combo, true, true, 0);
+
+        let priority_combo = gtk::ComboBoxText::new();
+        priority_combo.append(Some(&PRIORITY_NONE.to_string()), &gettext("None"));
+        priority_combo.append(Some(&PRIORITY_LOW.to_string()), &gettext("Low"));
+        priority_combo.append(Some(&PRIORITY_MEDIUM.to_string()), &gettext("Medium"));
+        priority_combo.append(Some(&PRIORITY_HIGH.to_string()), &gettext("High"));
+        priority_combo.set_active_id(Some(&PRIORITY_NONE.to_string()));
+        list_box.pack_start(&gtk::Label::builder().label(&format!("<b>{}</b>", gettext("Priority:"))).use_markup(true).build(), false, false, 0);
+        list_box.pack_start(&priority_combo, false, false, 0);
+
+        dialog.content_area().pack_start(&title_label, false, false, 0);
+        dialog.content_area().pack_start(&todo_title_entry, false, false, 0);
+        dialog.content_area().pack_start(&list_box, false, false, 0);
+        dialog.content_area().pack_start(&notes_label, false, false, 0);
+        dialog.content_area().pack_start(&todo_notes_frame, true, true, 0);
+
+        let start_picker = StartPicker::new();
         let mut time_picker = None;
         if have_timepicker {
             let real_time_picker = Timepicker::new();
             dialog.content_area().pack_start(&real_time_picker.build_ui(), false, false, 0);
+
+            let start_spin = start_picker.spin.clone();
+            real_time_picker.all_day_check.connect_toggled(move |c| {
+                start_spin.set_all_day(c.is_active()); // 全天的 todo 开始时间也只有日期
+            });
+
+            // 时区无法识别时不能保存, 以免悄悄按本地时间保存
+            let save_button_clone = save_button.clone();
+            let time_picker_clone = real_time_picker.clone();
+            real_time_picker.connect_validity_changed(move || {
+                save_button_clone.set_sensitive(time_picker_clone.is_valid());
+            });
             time_picker = Some(real_time_picker);
         }
+        dialog.content_area().pack_start(&start_picker.build_ui(), false, false, 0);
+
+        let alarm_picker = AlarmPicker::new(have_timepicker);
+        dialog.content_area().pack_start(&alarm_picker.build_ui(), false, false, 0);
+
+        let attachment_picker = AttachmentPicker::new();
+        dialog.content_area().pack_start(&attachment_picker.build_ui(), false, false, 0);
 
         dialog.content_area().pack_start(&button_box, false, false, 0);
 
         let dialog = ReminderEditDialog {
             dialog: Rc::new(dialog),
-            todo_content_view: Rc::new(todo_content_view),
+            todo_title_entry: Rc::new(todo_title_entry),
+            list_combo: Rc::new(list_combo),
+            priority_combo: Rc::new(priority_combo),
+            todo_notes_view: Rc::new(todo_notes_view),
             todo_timepicker: Rc::new(time_picker),
+            start_picker: Rc::new(start_picker),
+            alarm_picker: Rc::new(alarm_picker),
+            attachment_picker: Rc::new(attachment_picker),
             save_todo: Rc::new(RefCell::new(false)),
         };
 
@@ -170,8 +762,12 @@ impl ReminderEditDialog {
         });
 
         let self_clone = dialog.clone();
-        dialog.dialog.connect_key_press_event(move |_, e| {
-            if e.keyval() == gtk::gdk::keys::constants::Return {
+        dialog.dialog.connect_key_press_event(move |d, e| {
+            // 备注中的回车用于换行, 链接输入框中的回车用于添加链接
+            let keep_open = d.focused_widget().map_or(false, |w| {
+                w.is::<gtk::TextView>() || w == self_clone.attachment_picker.url_entry.clone().upcast::<Widget>()
+            });
+            if e.keyval() == gtk::gdk::keys::constants::Return && !keep_open && self_clone.can_save() {
                 *self_clone.save_todo.deref().borrow_mut() = true;
                 self_clone.dialog.hide();
             }
@@ -181,30 +777,100 @@ impl ReminderEditDialog {
         return dialog;
     }
 
+    fn can_save(&self) -> bool {
+        self.todo_timepicker.deref().as_ref().map_or(true, |todo_timepicker| todo_timepicker.is_valid())
+    }
+
     pub fn show(&self) {
         self.dialog.show_all();
+        self.alarm_picker.update_kind();
+        if let Some(todo_timepicker) = self.todo_timepicker.deref() {
+            todo_timepicker.update_all_day(); // show_all 会把隐藏的时分重新显示出来
+            self.start_picker.spin.set_all_day(todo_timepicker.all_day_check.is_active());
+        }
     }
 
-    pub fn set_time(&self, time: DateTime<Local>) {
+    // 年份范围, 窗口位置和自然语言的默认时间
+    pub fn apply_settings(&self, settings: &Settings) {
+        self.dialog.set_window_position(settings.window_position.to_gtk());
+        if let Some(todo_timepicker) = self.todo_timepicker.deref() {
+            todo_timepicker.spin.set_year_range(settings.min_year, settings.max_year);
+            todo_timepicker.default_time.set(settings.default_time);
+        }
+        self.start_picker.spin.set_year_range(settings.min_year, settings.max_year);
+        self.alarm_picker.spin.set_year_range(settings.min_year, settings.max_year);
+    }
+
+    pub fn set_time(&self, time: TodoTime) {
         let todo_timepicker: &Option<Timepicker> = self.todo_timepicker.borrow();
         match todo_timepicker {
             Some(todo_timepicker) => {
-                todo_timepicker.set_time(time);
+                todo_timepicker.set_todo_time(time);
             }
             None => {}
         };
     }
 
+    pub fn set_start(&self, start: Option<TodoStart>) {
+        self.start_picker.set_start(start);
+    }
+
+    pub fn get_start(&self) -> Option<TodoStart> {
+        self.start_picker.get_start()
+    }
+
+    pub fn set_alarms(&self, alarms: Vec<NewAlarm>) {
+        self.alarm_picker.set_alarms(alarms);
+    }
+
+    pub fn get_alarms(&self) -> Vec<NewAlarm> {
+        self.alarm_picker.get_alarms()
+    }
+
+    pub fn set_attachments(&self, attachments: Vec<NewAttachment>) {
+        self.attachment_picker.set_attachments(attachments);
+    }
+
+    pub fn get_attachments(&self) -> Vec<NewAttachment> {
+        self.attachment_picker.get_attachments()
+    }
+
+    // list_id 为 None 时选中 "None"
+    pub fn set_lists(&self, lists: &Vec<TodoList>, list_id: Option<i32>) {
+        self.list_combo.remove_all();
+        self.list_combo.append(Some(""), &gettext("None"));
+        for list in lists.iter() {
+            self.list_combo.append(Some(&list.id.to_string()), &list.name);
+        }
+        self.list_combo.set_active_id(Some(&list_id.map_or(String::new(), |id| id.to_string())));
+    }
+
+    pub fn get_list(&self) -> Option<i32> {
+        self.list_combo.active_id().and_then(|id| id.parse::<i32>().ok())
+    }
+
+    pub fn set_priority(&self, priority: i32) {
+        self.priority_combo.set_active_id(Some(&priority.to_string()));
+    }
+
+    pub fn get_priority(&self) -> i32 {
+        self.priority_combo.active_id().and_then(|id| id.parse::<i32>().ok()).unwrap_or(PRIORITY_NONE)
+    }
+
     pub fn set_content(&self, content: String) {
-        let buffer = gtk::TextBuffer::builder().text(&content).build();
-        self.todo_content_view.set_buffer(Some(&buffer));
+        self.todo_title_entry.set_text(&content);
     }
 
-    pub fn get_time(&self) -> Option<DateTime<Local>> {
+    pub fn set_notes(&self, notes: String) {
+        let buffer = gtk::TextBuffer::builder().text(&notes).build();
+        self.todo_notes_view.set_buffer(Some(&buffer));
+    }
+
+    pub fn get_time(&self) -> Option<TodoTime> {
         let todo_timepicker: &Option<Timepicker> = self.todo_timepicker.borrow();
         match todo_timepicker {
             Some(todo_timepicker) => {
-                Some(todo_timepicker.get_time())
+                Some(todo_timepicker.get_todo_time())
             }
             None => {
                 None
@@ -212,20 +878,11 @@ impl ReminderEditDialog {
         }
     }
 
-    pub fn connect_hide<F: 'static>(&self, f: F) where F: Fn(bool, String, Option<DateTime<Local>>) {
+    pub fn connect_hide<F: 'static>(&self, f: F) where F: Fn(bool, TodoEdit) {
         let self_clone = self.clone();
         self.dialog.connect_hide(move |_| {
-            let todo_timepicker: &Option<Timepicker> = self_clone.todo_timepicker.borrow();
-            let time = match todo_timepicker {
-                Some(todo_timepicker) => {
-                    Some(todo_timepicker.get_time())
-                }
-                None => {
-                    None
-                }
-            };
-
-            let text = match self_clone.todo_content_view.buffer() {
+            let title = self_clone.todo_title_entry.text().trim().to_string();
+            let notes = match self_clone.todo_notes_view.buffer() {
                 Some(buffer) => {
                     let (start, end) = buffer.bounds();
 
@@ -243,7 +900,16 @@ impl ReminderEditDialog {
                 }
             };
 
-            f(*self_clone.save_todo.borrow_mut(), text, time);
+            f(*self_clone.save_todo.borrow_mut(), TodoEdit {
+                content: title,
+                notes: notes,
+                time: self_clone.get_time(),
+                start: self_clone.get_start(),
+                alarms: self_clone.get_alarms(),
+                attachments: self_clone.get_attachments(),
+                list_id: self_clone.get_list(),
+                priority: self_clone.get_priority(),
+            });
         });
     }
 }
\ No newline at end of file
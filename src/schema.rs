@@ -1,7 +0,0 @@
-table! {
-    todo(id) {
-        id -> Integer,
-        content -> Text,
-        expire_time -> Nullable<Timestamp>,
-    }
-}
\ No newline at end of file
@@ -0,0 +1,74 @@
+use std::fs::{create_dir_all, write};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use gtk::glib;
+
+const APPLICATION_ID: &str = "com.rmb122.reminder";
+const SERVICE_NAME: &str = "rust-reminder.service";
+const DESKTOP_NAME: &str = "rust-reminder.desktop";
+
+// systemd 和 desktop 文件的 Exec 都按空格分割参数, 路径中有空格时需要加引号
+fn quote_exec(exe: &Path) -> String {
+    let exe = exe.to_string_lossy();
+    if !exe.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+        return exe.to_string();
+    }
+    return format!("\"{}\"", exe.replace('\\', "\\\\").replace('"', "\\\""));
+}
+
+// 由 D-Bus 激活的用户服务, 没有窗口, 需要时再打开
+fn systemd_unit(exe: &Path) -> String {
+    format!("[Unit]
+Description=Rust Reminder
+PartOf=graphical-session.target
+After=graphical-session.target
+
+[Service]
+Type=dbus
+BusName={}
+ExecStart={} --gapplication-service
+Restart=on-failure
+
+[Install]
+WantedBy=graphical-session.target
+", APPLICATION_ID, quote_exec(exe))
+}
+
+// 有人调用 com.rmb122.reminder 而实例没有运行时, dbus-daemon 通过 systemd 启动上面的服务, 没有 systemd 时直接运行 Exec
+fn dbus_service(exe: &Path) -> String {
+    format!("[D-BUS Service]
+Name={}
+Exec={} --gapplication-service
+SystemdService={}
+", APPLICATION_ID, quote_exec(exe), SERVICE_NAME)
+}
+
+// 不使用 systemd 的桌面环境登录时以后台模式启动
+fn autostart_desktop(exe: &Path) -> String {
+    format!("[Desktop Entry]
+Type=Application
+Name=Reminder
+Comment=Keep reminders running in the background
+Exec={} --daemon
+NoDisplay=true
+X-GNOME-Autostart-enabled=true
+", quote_exec(exe))
+}
+
+// 根据当前可执行文件的路径生成 systemd 用户服务, D-Bus 激活文件和 XDG 自启动文件, 返回写入的文件
+pub fn install_service_files() -> io::Result<Vec<PathBuf>> {
+    let exe = std::env::current_exe()?;
+    let config_dir = glib::user_config_dir();
+    let files = vec![
+        (config_dir.join("systemd/user").join(SERVICE_NAME), systemd_unit(&exe)),
+        (glib::user_data_dir().join("dbus-1/services").join(format!("{}.service", APPLICATION_ID)), dbus_service(&exe)),
+        (config_dir.join("autostart").join(DESKTOP_NAME), autostart_desktop(&exe)),
+    ];
+
+    for (path, content) in files.iter() {
+        create_dir_all(path.parent().unwrap())?;
+        write(path, content)?;
+    }
+    return Ok(files.into_iter().map(|(path, _)| path).collect());
+}
@@ -0,0 +1,184 @@
+use std::fs::{create_dir_all, read_to_string, write};
+use std::io;
+use std::path::PathBuf;
+
+use chrono::NaiveTime;
+use gettextrs::gettext;
+use gtk::glib;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use reminder_core::NewAlarm;
+
+const CONFIG_FILE: &str = "rust-reminder/config.toml";
+const TIME_FORMAT: &str = "%H:%M";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowPosition {
+    Mouse,
+    Center,
+    None,
+}
+
+impl WindowPosition {
+    pub fn to_gtk(self) -> gtk::WindowPosition {
+        match self {
+            WindowPosition::Mouse => gtk::WindowPosition::Mouse,
+            WindowPosition::Center => gtk::WindowPosition::Center,
+            WindowPosition::None => gtk::WindowPosition::None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TimeFormat {
+    #[serde(rename = "24h")]
+    Hour24,
+    #[serde(rename = "12h")]
+    Hour12,
+}
+
+impl TimeFormat {
+    // g_date_time_format 的格式字符串, 翻译中可以调整顺序
+    pub fn pattern(self) -> String {
+        match self {
+            // Translators: 24 小时制的时间, 格式见 g_date_time_format
+            TimeFormat::Hour24 => gettext("%H:%M"),
+            // Translators: 12 小时制的时间, 例如 "%p %I:%M"
+            TimeFormat::Hour12 => gettext("%I:%M %p"),
+        }
+    }
+}
+
+// 保存在 ~/.config/rust-reminder/config.toml, 文件中没写的项使用默认值
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub database_path: Option<String>, // 修改后马上切换, 新的数据库打不开时保留原来的路径
+    pub window_width: i32,
+    pub calendar_width: i32,
+    pub window_position: WindowPosition,
+    pub min_year: i32,
+    pub max_year: i32,
+    pub default_alarm_minutes: Option<i32>, // 新建 todo 时默认提前多少分钟提醒
+    pub time_format: TimeFormat,
+    #[serde(serialize_with = "serialize_time", deserialize_with = "deserialize_time")]
+    pub default_time: NaiveTime, // 新建 todo 时的默认时间
+    pub show_completed: bool, // 为 false 时列表中不显示已完成的 todo
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            database_path: None,
+            window_width: 600,
+            calendar_width: 250,
+            window_position: WindowPosition::Mouse,
+            min_year: 1970,
+            max_year: 2077,
+            default_alarm_minutes: None,
+            time_format: TimeFormat::Hour24,
+            default_time: NaiveTime::from_hms(9, 0, 0),
+            show_completed: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn config_path() -> PathBuf {
+        glib::user_config_dir().join(CONFIG_FILE)
+    }
+
+    // 没有配置文件时使用默认值, 格式错误时也使用默认值, 但不会覆盖原来的文件
+    pub fn load() -> Settings {
+        let path = Settings::config_path();
+        let content = match read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => return Settings::default(),
+        };
+        match Settings::parse(&content) {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", path.display(), e);
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Settings, toml::de::Error> {
+        let settings: Settings = toml::from_str(content)?;
+        if settings.min_year > settings.max_year {
+            return Ok(Settings { min_year: settings.max_year, max_year: settings.min_year, ..settings });
+        }
+        return Ok(settings);
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Settings::config_path();
+        let content = toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        create_dir_all(path.parent().unwrap())?;
+        write(&path, content)
+    }
+
+    // 新建的 todo 有日期时才会加上默认提醒
+    pub fn default_alarms(&self) -> Vec<NewAlarm> {
+        match self.default_alarm_minutes {
+            Some(minutes) => vec![NewAlarm { trigger_time: None, trigger_offset: Some(minutes) }],
+            None => vec![],
+        }
+    }
+}
+
+fn serialize_time<S: Serializer>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&time.format(TIME_FORMAT).to_string())
+}
+
+fn deserialize_time<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    NaiveTime::parse_from_str(&text, TIME_FORMAT)
+        .map_err(|_| serde::de::Error::custom(format!("invalid time {}, expected HH:MM", text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+
+    use super::{Settings, TimeFormat, WindowPosition};
+
+    #[test]
+    fn missing_values_use_defaults() {
+        let settings = Settings::parse("time_format = \"12h\"\ndefault_time = \"08:30\"\n").unwrap();
+        assert_eq!(settings.time_format, TimeFormat::Hour12);
+        assert_eq!(settings.default_time, NaiveTime::from_hms(8, 30, 0));
+        assert_eq!(settings.window_width, 600);
+        assert_eq!(settings.window_position, WindowPosition::Mouse);
+        assert_eq!(settings.default_alarm_minutes, None);
+        assert!(settings.show_completed);
+        assert_eq!(Settings::parse("").unwrap(), Settings::default());
+        // 已经去掉的设置项不影响读取
+        assert_eq!(Settings::parse("first_day_of_week = \"sunday\"").unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn round_trip() {
+        let settings = Settings {
+            database_path: Some(String::from("~/todo.db")),
+            window_position: WindowPosition::Center,
+            default_alarm_minutes: Some(15),
+            show_completed: false,
+            ..Settings::default()
+        };
+        let content = toml::to_string(&settings).unwrap();
+        assert!(content.contains("show_completed = false"));
+        assert!(content.contains("default_time = \"09:00\""));
+        assert_eq!(Settings::parse(&content).unwrap(), settings);
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert!(Settings::parse("default_time = \"9am\"").is_err());
+        assert!(Settings::parse("time_format = \"36h\"").is_err());
+        let settings = Settings::parse("min_year = 2100\nmax_year = 2000").unwrap();
+        assert_eq!((settings.min_year, settings.max_year), (2000, 2100));
+    }
+}
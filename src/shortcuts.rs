@@ -0,0 +1,50 @@
+use gettextrs::gettext;
+use gtk::gdk;
+use gtk::prelude::*;
+
+// (action name, accelerators, description), 同时用于按键分发和快捷键窗口, description 在显示时翻译
+pub const SHORTCUTS: &[(&str, &[&str], &str)] = &[
+    ("new-todo", &["n"], N_!("New todo")),
+    ("edit", &["e", "Return"], N_!("Edit selected todo")),
+    ("complete", &["c"], N_!("Mark selected todos as done or not done")),
+    ("delete", &["Delete"], N_!("Remove selected todos")),
+    ("move-up", &["<Alt>Up"], N_!("Move selected todo up")),
+    ("move-down", &["<Alt>Down"], N_!("Move selected todo down")),
+    ("today", &["t"], N_!("Go to today")),
+    ("prev-day", &["Left", "bracketleft"], N_!("Previous day")),
+    ("next-day", &["Right", "bracketright"], N_!("Next day")),
+    ("search", &["slash"], N_!("Search todos")),
+    ("shortcuts", &["question"], N_!("Show keyboard shortcuts")),
+];
+
+// 快捷键大多没有修饰键, 不能直接用 set_accels_for_action, 否则输入框里打字也会触发, 所以手动分发
+pub fn find_shortcut_action(event: &gdk::EventKey) -> Option<&'static str> {
+    let modifier_mask = gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::MOD1_MASK;
+    let keyval = event.keyval().to_lower();
+
+    for (action, accels, _) in SHORTCUTS.iter() {
+        for accel in accels.iter() {
+            let (key, modifier) = gtk::accelerator_parse(accel);
+            if gdk::keys::Key::from(key).to_lower() == keyval && (event.state() & modifier_mask) == (modifier & modifier_mask) {
+                return Some(action);
+            }
+        }
+    }
+    None
+}
+
+pub fn get_shortcuts_window(parent: Option<&gtk::Window>) -> gtk::ShortcutsWindow {
+    let group = gtk::ShortcutsGroup::builder().title(&gettext("Reminder")).build();
+    for (_, accels, title) in SHORTCUTS.iter() {
+        let shortcut = gtk::ShortcutsShortcut::builder().accelerator(&accels.join(" ")).title(&gettext(*title)).build();
+        group.add(&shortcut);
+    }
+
+    let section = gtk::ShortcutsSection::builder().section_name("shortcuts").build();
+    section.add(&group);
+
+    let window = gtk::ShortcutsWindow::builder().modal(true).build();
+    window.set_transient_for(parent);
+    window.add(&section);
+    return window;
+}
@@ -0,0 +1,140 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+use gtk::{gdk, gio, glib};
+use gtk::prelude::*;
+
+use reminder_core::{PRIORITY_HIGH, PRIORITY_LOW, PRIORITY_MEDIUM, Todo};
+
+const DEFAULT_CSS: &str = include_str!("style.css");
+const USER_CSS_FILE: &str = "rust-reminder/style.css";
+
+pub fn user_css_path() -> PathBuf {
+    glib::user_config_dir().join(USER_CSS_FILE)
+}
+
+// 先加载自带的样式, 用户的样式优先级更高. 返回的 FileMonitor 需要一直保留, 用户的样式修改后自动重新加载
+pub fn load_css() -> Option<gio::FileMonitor> {
+    let screen = gdk::Screen::default()?;
+
+    let default_provider = gtk::CssProvider::new();
+    if let Err(e) = default_provider.load_from_data(DEFAULT_CSS.as_bytes()) {
+        eprintln!("Error loading default style: {}", e);
+    }
+    gtk::StyleContext::add_provider_for_screen(&screen, &default_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+
+    let user_provider = gtk::CssProvider::new();
+    load_user_css(&user_provider);
+    gtk::StyleContext::add_provider_for_screen(&screen, &user_provider, gtk::STYLE_PROVIDER_PRIORITY_USER);
+
+    let monitor = match gio::File::for_path(user_css_path()).monitor_file(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE) {
+        Ok(monitor) => monitor,
+        Err(e) => {
+            eprintln!("Error watching {}: {}", user_css_path().display(), e);
+            return None;
+        }
+    };
+    monitor.connect_changed(move |_, _, _, event| {
+        match event {
+            gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::Created | gio::FileMonitorEvent::Deleted => load_user_css(&user_provider),
+            _ => {}
+        }
+    });
+    Some(monitor)
+}
+
+// 文件不存在时清空, 有语法错误时只输出错误, 其余的规则仍然生效
+fn load_user_css(provider: &gtk::CssProvider) {
+    let path = user_css_path();
+    let result = match path.exists() {
+        true => provider.load_from_path(&path.to_string_lossy()),
+        false => provider.load_from_data(b""),
+    };
+    if let Err(e) = result {
+        eprintln!("Error loading {}: {}", path.display(), e);
+    }
+}
+
+// todo 行的 CSS class, 见 style.css
+pub fn get_todo_style_classes(todo: &Todo, now: &DateTime<Local>) -> Vec<&'static str> {
+    let mut classes = vec!["todo"];
+    let today = now.date().naive_local();
+    let overdue = match todo.all_day {
+        true => todo.local_date().map_or(false, |date| date < today),
+        false => todo.local_expire_time().map_or(false, |time| time < *now),
+    };
+    if todo.completed {
+        classes.push("done"); // 已完成的 todo 不算过期
+    } else if overdue {
+        classes.push("overdue");
+    }
+    if todo.is_on_date(today) {
+        classes.push("today");
+    }
+    if todo.expire_time.is_none() {
+        classes.push("no-date");
+    } else if todo.all_day {
+        classes.push("all-day");
+    }
+    match todo.priority {
+        PRIORITY_LOW => classes.push("priority-low"),
+        PRIORITY_MEDIUM => classes.push("priority-medium"),
+        PRIORITY_HIGH => classes.push("priority-high"),
+        _ => {}
+    }
+    return classes;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local, TimeZone};
+
+    use reminder_core::{PRIORITY_HIGH, PRIORITY_LOW, PRIORITY_NONE, Todo};
+
+    use super::get_todo_style_classes;
+
+    fn get_todo(expire_time: Option<chrono::NaiveDateTime>, all_day: bool) -> Todo {
+        Todo {
+            id: 1,
+            content: String::from("buy milk"),
+            expire_time: expire_time,
+            position: 0,
+            timezone: None,
+            all_day: all_day,
+            start_time: None,
+            hide_until_start: false,
+            notes: String::new(),
+            list_id: None,
+            completed: false,
+            priority: PRIORITY_NONE,
+        }
+    }
+
+    #[test]
+    fn style_classes() {
+        let now = Local.ymd(2026, 10, 20).and_hms(12, 0, 0);
+        let utc_now = now.naive_utc();
+
+        assert_eq!(get_todo_style_classes(&get_todo(None, false), &now), vec!["todo", "no-date"]);
+        assert_eq!(get_todo_style_classes(&get_todo(Some(utc_now - Duration::hours(1)), false), &now), vec!["todo", "overdue", "today"]);
+        assert_eq!(get_todo_style_classes(&get_todo(Some(utc_now + Duration::hours(1)), false), &now), vec!["todo", "today"]);
+        assert_eq!(get_todo_style_classes(&get_todo(Some(utc_now + Duration::days(2)), false), &now), vec!["todo"]);
+
+        // 全天的 todo 当天不算过期
+        let today = now.date().naive_local().and_hms(0, 0, 0);
+        assert_eq!(get_todo_style_classes(&get_todo(Some(today), true), &now), vec!["todo", "today", "all-day"]);
+        assert_eq!(get_todo_style_classes(&get_todo(Some(today - Duration::days(1)), true), &now), vec!["todo", "overdue", "all-day"]);
+
+        let mut done = get_todo(Some(utc_now - Duration::hours(1)), false);
+        done.completed = true;
+        assert_eq!(get_todo_style_classes(&done, &now), vec!["todo", "done", "today"]);
+        done.expire_time = None;
+        assert_eq!(get_todo_style_classes(&done, &now), vec!["todo", "done", "no-date"]);
+
+        let mut important = get_todo(None, false);
+        important.priority = PRIORITY_HIGH;
+        assert_eq!(get_todo_style_classes(&important, &now), vec!["todo", "no-date", "priority-high"]);
+        important.priority = PRIORITY_LOW;
+        assert_eq!(get_todo_style_classes(&important, &now), vec!["todo", "no-date", "priority-low"]);
+    }
+}
@@ -0,0 +1,360 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use gettextrs::{gettext, ngettext};
+use gtk::{gio, glib};
+use gtk::prelude::*;
+
+use crate::dbus_service::register_object;
+use crate::i18n::fill_placeholders;
+
+// StatusNotifierItem 托盘图标, 菜单通过 com.canonical.dbusmenu 导出
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU_PATH: &str = "/StatusNotifierItem/Menu";
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+const MENU_INTERFACE: &str = "com.canonical.dbusmenu";
+const WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
+
+const ITEM_XML: &str = r#"
+<node>
+  <interface name="org.kde.StatusNotifierItem">
+    <property name="Category" type="s" access="read"/>
+    <property name="Id" type="s" access="read"/>
+    <property name="Title" type="s" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="IconName" type="s" access="read"/>
+    <property name="ToolTip" type="(sa(iiay)ss)" access="read"/>
+    <property name="ItemIsMenu" type="b" access="read"/>
+    <property name="Menu" type="o" access="read"/>
+    <property name="XAyatanaLabel" type="s" access="read"/>
+    <method name="Activate">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="SecondaryActivate">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="ContextMenu">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="Scroll">
+      <arg name="delta" type="i" direction="in"/>
+      <arg name="orientation" type="s" direction="in"/>
+    </method>
+    <signal name="NewTitle"/>
+    <signal name="NewIcon"/>
+    <signal name="NewToolTip"/>
+    <signal name="XAyatanaNewLabel">
+      <arg name="label" type="s"/>
+      <arg name="guide" type="s"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+const MENU_XML: &str = r#"
+<node>
+  <interface name="com.canonical.dbusmenu">
+    <property name="Version" type="u" access="read"/>
+    <property name="TextDirection" type="s" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="IconThemePath" type="as" access="read"/>
+    <method name="GetLayout">
+      <arg name="parentId" type="i" direction="in"/>
+      <arg name="recursionDepth" type="i" direction="in"/>
+      <arg name="propertyNames" type="as" direction="in"/>
+      <arg name="revision" type="u" direction="out"/>
+      <arg name="layout" type="(ia{sv}av)" direction="out"/>
+    </method>
+    <method name="GetGroupProperties">
+      <arg name="ids" type="ai" direction="in"/>
+      <arg name="propertyNames" type="as" direction="in"/>
+      <arg name="properties" type="a(ia{sv})" direction="out"/>
+    </method>
+    <method name="GetProperty">
+      <arg name="id" type="i" direction="in"/>
+      <arg name="name" type="s" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+    </method>
+    <method name="Event">
+      <arg name="id" type="i" direction="in"/>
+      <arg name="eventId" type="s" direction="in"/>
+      <arg name="data" type="v" direction="in"/>
+      <arg name="timestamp" type="u" direction="in"/>
+    </method>
+    <method name="EventGroup">
+      <arg name="events" type="a(isvu)" direction="in"/>
+      <arg name="idErrors" type="ai" direction="out"/>
+    </method>
+    <method name="AboutToShow">
+      <arg name="id" type="i" direction="in"/>
+      <arg name="needUpdate" type="b" direction="out"/>
+    </method>
+    <method name="AboutToShowGroup">
+      <arg name="ids" type="ai" direction="in"/>
+      <arg name="updatesNeeded" type="ai" direction="out"/>
+      <arg name="idErrors" type="ai" direction="out"/>
+    </method>
+    <signal name="LayoutUpdated">
+      <arg name="revision" type="u"/>
+      <arg name="parent" type="i"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+// 点击托盘图标或菜单项后要做的事
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrayAction {
+    Open,
+    QuickAdd,
+    ShowTodo(i32),
+    Quit,
+}
+
+// id 为 0 的是根菜单
+#[derive(Clone, Debug, PartialEq)]
+enum MenuItem {
+    Action(String, TrayAction),
+    Label(String),
+    Separator,
+}
+
+// 过期的和今天的 todo 分开列出, 都没有时才显示 "Nothing due today"
+fn get_menu_items(overdue: &[(i32, String)], today: &[(i32, String)]) -> Vec<MenuItem> {
+    let mut items = vec![
+        MenuItem::Action(gettext("Open Reminder"), TrayAction::Open),
+        MenuItem::Action(gettext("Quick add..."), TrayAction::QuickAdd),
+        MenuItem::Separator,
+    ];
+    if overdue.is_empty() && today.is_empty() {
+        items.push(MenuItem::Label(gettext("Nothing due today")));
+    }
+    for (title, todos) in [(gettext("Overdue"), overdue), (gettext("Today"), today)] {
+        if todos.is_empty() {
+            continue;
+        }
+        items.push(MenuItem::Label(title));
+        for (todo_id, text) in todos.iter() {
+            items.push(MenuItem::Action(text.clone(), TrayAction::ShowTodo(*todo_id)));
+        }
+    }
+    items.push(MenuItem::Separator);
+    items.push(MenuItem::Action(gettext("Quit"), TrayAction::Quit));
+    return items;
+}
+
+fn get_item_properties(item: &MenuItem) -> HashMap<String, glib::Variant> {
+    let mut properties = HashMap::new();
+    match item {
+        MenuItem::Action(label, _) => {
+            properties.insert(String::from("label"), label.replace('_', "__").to_variant()); // 下划线是快捷键前缀
+        }
+        MenuItem::Label(label) => {
+            properties.insert(String::from("label"), label.replace('_', "__").to_variant());
+            properties.insert(String::from("enabled"), false.to_variant());
+        }
+        MenuItem::Separator => {
+            properties.insert(String::from("type"), "separator".to_variant());
+        }
+    }
+    return properties;
+}
+
+// 菜单只有一层, 子菜单项的 id 从 1 开始
+fn get_menu_layout(items: &Vec<MenuItem>) -> glib::Variant {
+    let children: Vec<glib::Variant> = items.iter().enumerate()
+        .map(|(i, item)| (i as i32 + 1, get_item_properties(item), Vec::<glib::Variant>::new()).to_variant())
+        .collect();
+    let mut root_properties = HashMap::new();
+    root_properties.insert(String::from("children-display"), "submenu".to_variant());
+    return (0i32, root_properties, children).to_variant();
+}
+
+struct TrayState {
+    due_count: usize,
+    items: Vec<MenuItem>,
+    revision: u32,
+}
+
+#[derive(Clone)]
+pub struct TrayIcon {
+    connection: gio::DBusConnection,
+    state: Rc<RefCell<TrayState>>,
+}
+
+impl TrayIcon {
+    pub fn new<F: Fn(TrayAction) + 'static>(connection: &gio::DBusConnection, on_action: F) -> Result<TrayIcon, glib::Error> {
+        let tray = TrayIcon {
+            connection: connection.clone(),
+            state: Rc::new(RefCell::new(TrayState { due_count: 0, items: get_menu_items(&[], &[]), revision: 1 })),
+        };
+        let on_action: Rc<dyn Fn(TrayAction)> = Rc::new(on_action);
+
+        let on_action_clone = on_action.clone();
+        let tray_clone = tray.clone();
+        register_object(connection, ITEM_PATH, ITEM_XML, ITEM_INTERFACE, move |method, _, invocation| {
+            if method == "Activate" {
+                on_action_clone(TrayAction::Open);
+            }
+            invocation.return_value(None);
+        }, move |property| tray_clone.get_item_property(property))?;
+
+        let tray_clone = tray.clone();
+        register_object(connection, MENU_PATH, MENU_XML, MENU_INTERFACE, move |method, parameters, invocation| {
+            let result = tray_clone.call_menu_method(method, parameters, &on_action);
+            invocation.return_value(result.as_ref());
+        }, |property| match property {
+            "Version" => Some(3u32.to_variant()),
+            "TextDirection" => Some("ltr".to_variant()),
+            "Status" => Some("normal".to_variant()),
+            "IconThemePath" => Some(Vec::<String>::new().to_variant()),
+            _ => None,
+        })?;
+
+        // 托盘 (状态栏) 可能比我们晚启动或者重启, 每次出现时都要重新注册
+        let service = connection.unique_name().map(|name| name.to_string()).unwrap_or_default();
+        gio::bus_watch_name_on_connection(connection, WATCHER_NAME, gio::BusNameWatcherFlags::NONE, move |connection, _, _| {
+            connection.call(Some(WATCHER_NAME), "/StatusNotifierWatcher", WATCHER_NAME, "RegisterStatusNotifierItem",
+                            Some(&(service.as_str(),).to_variant()), None, gio::DBusCallFlags::NONE, -1, gio::Cancellable::NONE, |result| {
+                    if let Err(e) = result {
+                        eprintln!("Error registering tray icon: {}", e);
+                    }
+                });
+        }, |_, _| {});
+
+        return Ok(tray);
+    }
+
+    fn icon_name(&self) -> &'static str {
+        match self.state.borrow().due_count {
+            0 => "appointment-soon",
+            _ => "appointment-missed",
+        }
+    }
+
+    fn label(&self) -> String {
+        match self.state.borrow().due_count {
+            0 => String::new(),
+            count => count.to_string(),
+        }
+    }
+
+    fn tooltip(&self) -> String {
+        match self.state.borrow().due_count {
+            0 => gettext("Nothing due today"),
+            count => fill_placeholders(&ngettext("{} todo due today or overdue", "{} todos due today or overdue", count as u32), &[&count.to_string()]),
+        }
+    }
+
+    fn get_item_property(&self, property: &str) -> Option<glib::Variant> {
+        match property {
+            "Category" => Some("ApplicationStatus".to_variant()),
+            "Id" => Some("rust-reminder".to_variant()),
+            "Title" => Some(gettext("Reminder").to_variant()),
+            "Status" => Some("Active".to_variant()),
+            "IconName" => Some(self.icon_name().to_variant()),
+            "ToolTip" => Some((self.icon_name(), Vec::<(i32, i32, Vec<u8>)>::new(), gettext("Reminder"), self.tooltip()).to_variant()),
+            "ItemIsMenu" => Some(false.to_variant()), // 左键打开窗口, 右键显示菜单
+            "Menu" => glib::Variant::parse(Some(glib::VariantTy::OBJECT_PATH), &format!("'{}'", MENU_PATH)).ok(),
+            "XAyatanaLabel" => Some(self.label().to_variant()),
+            _ => None,
+        }
+    }
+
+    fn call_menu_method(&self, method: &str, parameters: &glib::Variant, on_action: &Rc<dyn Fn(TrayAction)>) -> Option<glib::Variant> {
+        let state = self.state.borrow();
+        match method {
+            "GetLayout" => Some(glib::Variant::tuple_from_iter([state.revision.to_variant(), get_menu_layout(&state.items)])),
+            "GetGroupProperties" => {
+                let (ids, _) = parameters.get::<(Vec<i32>, Vec<String>)>()?;
+                let properties: Vec<(i32, HashMap<String, glib::Variant>)> = ids.iter()
+                    .filter_map(|id| state.items.get((*id - 1) as usize).map(|item| (*id, get_item_properties(item))))
+                    .collect();
+                Some((properties,).to_variant())
+            }
+            "GetProperty" => {
+                let (id, name) = parameters.get::<(i32, String)>()?;
+                let value = state.items.get((id - 1) as usize).and_then(|item| get_item_properties(item).remove(&name));
+                Some((value.unwrap_or_else(|| "".to_variant()),).to_variant())
+            }
+            "Event" => {
+                let (id, event, _, _) = parameters.get::<(i32, String, glib::Variant, u32)>()?;
+                let action = match (state.items.get((id - 1) as usize), event.as_str()) {
+                    (Some(MenuItem::Action(_, action)), "clicked") => Some(*action),
+                    _ => None,
+                };
+                drop(state); // 回调中会刷新菜单
+                if let Some(action) = action {
+                    on_action(action);
+                }
+                None
+            }
+            "EventGroup" => Some((Vec::<i32>::new(),).to_variant()),
+            "AboutToShow" => Some((false,).to_variant()),
+            "AboutToShowGroup" => Some((Vec::<i32>::new(), Vec::<i32>::new()).to_variant()),
+            _ => None,
+        }
+    }
+
+    // overdue 和 today 为需要在菜单中列出的 todo 的 id 和文字
+    pub fn update(&self, due_count: usize, overdue: &[(i32, String)], today: &[(i32, String)]) {
+        {
+            let mut state = self.state.borrow_mut();
+            let items = get_menu_items(overdue, today);
+            if state.due_count == due_count && state.items == items {
+                return;
+            }
+            state.due_count = due_count;
+            state.items = items;
+            state.revision += 1;
+        }
+
+        let label = self.label();
+        let revision = self.state.borrow().revision;
+        let signals = vec![
+            (ITEM_PATH, ITEM_INTERFACE, "NewIcon", None),
+            (ITEM_PATH, ITEM_INTERFACE, "NewToolTip", None),
+            (ITEM_PATH, ITEM_INTERFACE, "XAyatanaNewLabel", Some((label.as_str(), "999").to_variant())),
+            (MENU_PATH, MENU_INTERFACE, "LayoutUpdated", Some((revision, 0i32).to_variant())),
+        ];
+        for (path, interface, signal, parameters) in signals.iter() {
+            if let Err(e) = self.connection.emit_signal(None, path, interface, signal, parameters.as_ref()) {
+                eprintln!("Error emitting {}: {}", signal, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gtk::glib;
+    use gtk::prelude::*;
+
+    use super::{get_menu_items, get_menu_layout, MenuItem};
+
+    #[test]
+    fn menu_layout() {
+        let layout = get_menu_layout(&get_menu_items(&[], &[(7, String::from("buy_milk"))]));
+        assert_eq!(layout.type_().as_str(), "(ia{sv}av)");
+        assert_eq!(layout.child_value(2).n_children(), 7);
+        let todo_item = layout.child_value(2).child_value(4).as_variant().unwrap();
+        assert_eq!(todo_item.child_value(0).get::<i32>(), Some(5));
+        let properties = glib::VariantDict::new(Some(&todo_item.child_value(1)));
+        assert_eq!(properties.lookup::<String>("label").unwrap(), Some(String::from("buy__milk")));
+    }
+
+    #[test]
+    fn nothing_due_only_without_todos() {
+        let labels = |items: Vec<MenuItem>| items.into_iter().filter_map(|item| match item {
+            MenuItem::Label(label) => Some(label),
+            _ => None,
+        }).collect::<Vec<String>>();
+        assert_eq!(labels(get_menu_items(&[], &[])), vec!["Nothing due today"]);
+        assert_eq!(labels(get_menu_items(&[(1, String::from("10-01  report"))], &[])), vec!["Overdue"]);
+        assert_eq!(labels(get_menu_items(&[(1, String::from("10-01  report"))], &[(2, String::from("call bob"))])), vec!["Overdue", "Today"]);
+    }
+}
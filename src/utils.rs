@@ -1,34 +1,117 @@
-use chrono::{Local, TimeZone};
-use gtk::{gdk_pixbuf, IconSize, pango};
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, Timelike, Weekday};
+use gettextrs::{gettext, ngettext};
+use gtk::{gdk, glib, IconSize, pango};
 use gtk::prelude::*;
 
-use crate::models::Todo;
+use reminder_core::{Todo, TodoList};
 
-pub fn get_icon_view(icon_list: &[&str]) -> Option<gtk::IconView> {
-    let icon_view = gtk::IconView::builder().
-        item_padding(5).columns(icon_list.len() as i32).
-        column_spacing(0).selection_mode(gtk::SelectionMode::Single).margin(0)
-        .build();
+use crate::i18n::fill_placeholders;
+use crate::markdown::{escape_markup, markdown_to_pango};
+use crate::settings::TimeFormat;
+use crate::theme::get_todo_style_classes;
 
-    let col_types = [gdk_pixbuf::Pixbuf::static_type()];
-    let model = gtk::ListStore::new(&col_types);
+pub fn get_action_button(icon: &str, action: &str, tooltip: &str) -> gtk::Button {
+    gtk::Button::builder().
+        image(&gtk::Image::from_icon_name(Some(icon), IconSize::Button)).
+        action_name(action).tooltip_text(tooltip).build()
+}
 
-    let icon_theme = gtk::IconTheme::default()?;
-    let (icon_size, _) = IconSize::Menu.lookup()?;
+pub fn show_error_dialog(parent: Option<&gtk::Window>, message: &str) {
+    let dialog = gtk::MessageDialog::new(parent, gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+                                         gtk::MessageType::Error, gtk::ButtonsType::Close, message);
+    dialog.run();
+    dialog.close();
+}
 
-    for icon in icon_list.iter() {
-        let icon = icon_theme.load_icon(icon, icon_size, gtk::IconLookupFlags::empty()).unwrap_or(None)?;
+pub fn open_uri(parent: Option<&gtk::Window>, uri: &str) {
+    if let Err(e) = gtk::show_uri_on_window(parent, uri, gtk::current_event_time()) {
+        show_error_dialog(parent, &fill_placeholders(&gettext("Error opening {}: {}"), &[uri, &e.to_string()]));
+    }
+}
 
-        model.insert_with_values(None, &[
-            (0, &icon)
-        ]);
+// 本地文件显示文件名, 其他的显示完整的 URI
+pub fn get_attachment_name(uri: &str) -> String {
+    match glib::filename_from_uri(uri) {
+        Ok((path, _)) => path.file_name().map_or(String::from(uri), |name| name.to_string_lossy().to_string()),
+        Err(_) => String::from(uri),
     }
+}
 
-    icon_view.set_model(Some(&model));
-    icon_view.set_pixbuf_column(0);
-    icon_view.set_activate_on_single_click(true);
+// 返回 true 表示用户确认
+pub fn ask_confirm(parent: Option<&gtk::Window>, message: &str) -> bool {
+    let dialog = gtk::MessageDialog::new(parent, gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+                                         gtk::MessageType::Question, gtk::ButtonsType::OkCancel, message);
+    let response = dialog.run();
+    dialog.close();
+    return response == gtk::ResponseType::Ok;
+}
+
+const TODO_DRAG_TARGET: &str = "application/x-rust-reminder-todo";
+const URI_LIST_TARGET: &str = "text/uri-list";
+
+pub fn get_todo_drag_targets() -> Vec<gtk::TargetEntry> {
+    vec![gtk::TargetEntry::new(TODO_DRAG_TARGET, gtk::TargetFlags::SAME_APP, 0)]
+}
 
-    Some(icon_view)
+// 文件管理器拖进来的文件和浏览器拖进来的链接都是 text/uri-list
+pub fn get_uri_drop_targets() -> Vec<gtk::TargetEntry> {
+    vec![gtk::TargetEntry::new(URI_LIST_TARGET, gtk::TargetFlags::OTHER_APP, 1)]
+}
+
+pub fn is_todo_drag(context: &gdk::DragContext) -> bool {
+    context.list_targets().iter().any(|target| target.name() == TODO_DRAG_TARGET)
+}
+
+pub fn get_dropped_todo_id(data: &gtk::SelectionData) -> Option<i32> {
+    String::from_utf8(data.data()).ok()?.parse::<i32>().ok()
+}
+
+// GtkCalendar 没有提供坐标到日期的接口, 只能按 gtk3 (gtkcalendar.c) 的布局来算, 依赖以下几点:
+// 1. 顶部是标题和星期栏, 高度固定, 等于最小高度减去 6 行日期的高度
+// 2. 剩下的空间平分给 6 行日期, 宽度平分给 7 列, 不显示周数 (显示周数时返回 None)
+// 3. 从右到左的语言中列的顺序相反
+// 4. 一周的第一天从 gtk30 的翻译中取, 第一行至少有一天是上个月的, 见 get_calendar_cell_date
+pub fn get_calendar_date_at(calendar: &gtk::Calendar, x: i32, y: i32) -> Option<NaiveDate> {
+    if calendar.shows_week_numbers() {
+        return None;
+    }
+
+    let (_, day_height) = calendar.create_pango_layout(Some("00")).pixel_size();
+    let (min_height, _) = calendar.preferred_height();
+    let top_height = min_height - day_height * 6;
+    let (row, mut column) = get_calendar_cell_at(calendar.allocated_width(), calendar.allocated_height(), top_height, x, y)?;
+    if calendar.direction() == gtk::TextDirection::Rtl {
+        column = 6 - column;
+    }
+
+    return get_calendar_cell_date(calendar.year(), (calendar.month() + 1) as u32, get_week_start().num_days_from_sunday(), row, column);
+}
+
+// 和 gtk3 的日历一样从 gtk30 的翻译里取一周的第一天, 日历没有设置它的接口, 自然语言解析也用这个值保持一致
+pub fn get_week_start() -> Weekday {
+    let week_start = glib::dgettext(Some("gtk30"), "calendar:week_start:0");
+    let days = week_start.trim_start_matches("calendar:week_start:").parse::<u32>().unwrap_or(0) % 7;
+    return (0..days).fold(Weekday::Sun, |weekday, _| weekday.succ());
+}
+
+// 返回 (行, 列), 都从 0 开始, 在标题和星期栏上时返回 None
+fn get_calendar_cell_at(width: i32, height: i32, top_height: i32, x: i32, y: i32) -> Option<(i32, i32)> {
+    let row_height = (height - top_height) / 6;
+    let column_width = width / 7;
+    if y < top_height || x < 0 || row_height <= 0 || column_width <= 0 {
+        return None;
+    }
+    Some((((y - top_height) / row_height).min(5), (x / column_width).min(6)))
+}
+
+// month 从 1 开始, week_start 中 0 为周日. 和 calendar_compute_days 相同, 这个月的第一天正好是一周的第一天时放在第二行
+fn get_calendar_cell_date(year: i32, month: u32, week_start: u32, row: i32, column: i32) -> Option<NaiveDate> {
+    let first_day = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let mut offset = (first_day.weekday().num_days_from_sunday() + 7 - week_start) % 7;
+    if offset == 0 {
+        offset = 7;
+    }
+    Some(first_day + Duration::days((row * 7 + column) as i64 - offset as i64))
 }
 
 pub fn get_border_label(label_str: &str, markup: bool) -> gtk::Frame {
@@ -46,26 +129,135 @@ pub fn get_border_label(label_str: &str, markup: bool) -> gtk::Frame {
     return frame;
 }
 
-pub fn get_todo_row_view(todo: &Todo) -> gtk::Grid {
+// 清单的颜色
+pub fn get_color_dot(color: &str) -> gtk::Label {
+    let dot = gtk::Label::new(None);
+    dot.set_markup(&format!("<span foreground=\"{}\">●</span>", escape_markup(color)));
+    return dot;
+}
+
+// list_color 为 None 时不显示清单的颜色
+pub fn get_todo_row_view(todo: &Todo, attachment_count: usize, list_color: Option<&str>, time_format: TimeFormat) -> gtk::Grid {
     let grid = gtk::Grid::new();
+    for class in get_todo_style_classes(todo, &Local::now()) {
+        grid.style_context().add_class(class);
+    }
+
+    let drag_handle = gtk::EventBox::new();
+    let handle_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+    handle_box.pack_start(&gtk::Image::from_icon_name(Some("list-drag-handle-symbolic"), IconSize::Menu), false, false, 0);
+    if let Some(color) = list_color {
+        handle_box.pack_start(&get_color_dot(color), false, false, 0);
+    }
+    drag_handle.add(&handle_box);
+    drag_handle.drag_source_set(gdk::ModifierType::BUTTON1_MASK, &get_todo_drag_targets(), gdk::DragAction::MOVE);
+    let todo_id = todo.id;
+    drag_handle.connect_drag_data_get(move |_, _, data, _, _| {
+        data.set(&data.target(), 8, todo_id.to_string().as_bytes());
+    });
+    grid.attach(&drag_handle, 0, 0, 1, 1);
 
-    let label = get_border_label(&todo.content, false);
+    // 链接由 GtkLabel 默认的 activate-link 处理, 用默认浏览器打开
+    let label = get_border_label(&markdown_to_pango(&todo.content), true);
     label.set_expand(true);
+    label.style_context().add_class("todo-title");
+    if !todo.notes.is_empty() {
+        label.set_tooltip_markup(Some(&markdown_to_pango(&todo.notes))); // 列表中只显示标题, 备注放在提示里
+    }
     grid.attach(&label, 1, 0, 1, 1);
 
-    if todo.expire_time.is_some() {
-        let label = get_border_label(&Local.from_local_datetime(&todo.expire_time.unwrap()).unwrap().format("%H:%M").to_string(), false);
+    if let Some(text) = get_todo_time_text(todo, time_format) {
+        let label = get_border_label(&text, false);
+        label.style_context().add_class("todo-time");
         grid.attach(&label, 2, 0, 1, 1);
     }
 
+    if attachment_count > 0 {
+        let clip = gtk::Image::from_icon_name(Some("mail-attachment-symbolic"), IconSize::Menu);
+        clip.set_tooltip_text(Some(&fill_placeholders(&ngettext("{} attachment", "{} attachments", attachment_count as u32), &[&attachment_count.to_string()])));
+        clip.set_margin_start(3);
+        grid.attach(&clip, 3, 0, 1, 1);
+    }
+
     unsafe {
         grid.set_data("todo", todo.clone());
     }
     return grid;
 }
 
+// 跨多天的 todo 显示起止日期, 同一天内的显示起止时间
+pub fn get_todo_time_text(todo: &Todo, time_format: TimeFormat) -> Option<String> {
+    let start_date = todo.local_start_date().filter(|date| Some(*date) != todo.local_date());
+    let start = todo.local_start_time().map(|time| time.naive_local());
+    // Translators: 跨天的 todo 显示的月和日, 格式见 g_date_time_format
+    let date_pattern = gettext("%m-%d");
+    let time_pattern = time_format.pattern();
+    let date_time_pattern = format!("{} {}", date_pattern, time_pattern);
+
+    let mut text = match (todo.local_expire_time().map(|time| time.naive_local()), start) {
+        (Some(time), _) if todo.all_day => match start_date {
+            Some(start_date) => format!("{} – {}", format_date_time(&start_date.and_hms(0, 0, 0), &date_pattern), format_date_time(&time, &date_pattern)),
+            None => gettext("all day"),
+        },
+        (Some(time), Some(start)) if start_date.is_some() => format!("{} – {}", format_date_time(&start, &date_time_pattern), format_date_time(&time, &date_time_pattern)),
+        (Some(time), Some(start)) => format!("{}–{}", format_date_time(&start, &time_pattern), format_date_time(&time, &time_pattern)),
+        (Some(time), None) => format_date_time(&time, &time_pattern),
+        (None, Some(start)) => fill_placeholders(&gettext("starts {}"), &[&format_date_time(&start, &date_time_pattern)]),
+        (None, None) => return None,
+    };
+
+    if let (Some(timezone), Some(wall_time)) = (&todo.timezone, todo.wall_expire_time()) {
+        text = format!("{} ({} {})", text, format_date_time(&wall_time, &time_pattern), timezone); // 同时显示 todo 自己时区的时间
+    }
+    return Some(text);
+}
+
+// 按当前 locale 格式化, 格式和 g_date_time_format 相同, 月份和上午下午这些名字也会跟着 locale 变化
+pub fn format_date_time(time: &NaiveDateTime, format: &str) -> String {
+    glib::DateTime::from_utc(time.year(), time.month() as i32, time.day() as i32, time.hour() as i32, time.minute() as i32, time.second() as f64)
+        .and_then(|time| time.format(format))
+        .map(|text| text.to_string())
+        .unwrap_or_else(|_| time.format(format).to_string())
+}
+
+// 当前 locale 习惯的日期格式, 例如 2026年10月20日, 10/20/2026
+pub fn format_date(date: NaiveDate) -> String {
+    format_date_time(&date.and_hms(0, 0, 0), "%x")
+}
+
+pub fn get_row_todo(row: &gtk::ListBoxRow) -> Option<Todo> {
+    unsafe {
+        row.child()?.data::<Todo>("todo").map(|todo| todo.as_ref().clone())
+    }
+}
+
+// list 为 None 时是 "All"
+pub fn get_list_row_view(list: Option<&TodoList>) -> gtk::Box {
+    let row = gtk::Box::builder().orientation(gtk::Orientation::Horizontal).spacing(6).margin(3).build();
+    row.style_context().add_class("todo-list");
+    match list {
+        Some(list) => {
+            row.pack_start(&get_color_dot(&list.color), false, false, 0);
+            row.pack_start(&gtk::Label::builder().label(&list.name).halign(gtk::Align::Start).ellipsize(pango::EllipsizeMode::End).build(), true, true, 0);
+            unsafe {
+                row.set_data("todo_list", list.clone());
+            }
+        }
+        None => {
+            row.pack_start(&gtk::Label::builder().label(&gettext("All")).halign(gtk::Align::Start).build(), true, true, 0);
+        }
+    }
+    return row;
+}
+
+pub fn get_row_list(row: &gtk::ListBoxRow) -> Option<TodoList> {
+    unsafe {
+        row.child()?.data::<TodoList>("todo_list").map(|list| list.as_ref().clone())
+    }
+}
+
 pub fn get_days_from_month(year: i32, month: u32) -> i64 {
-    Local.ymd(
+    NaiveDate::from_ymd(
         match month {
             12 => year + 1,
             _ => year,
@@ -76,6 +268,39 @@ pub fn get_days_from_month(year: i32, month: u32) -> i64 {
         },
         1,
     )
-        .signed_duration_since(Local.ymd(year, month, 1))
+        .signed_duration_since(NaiveDate::from_ymd(year, month, 1))
         .num_days()
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{get_calendar_cell_at, get_calendar_cell_date};
+
+    #[test]
+    fn calendar_cell_at() {
+        // 宽 280, 高 260, 顶部 50, 每行 35, 每列 40
+        assert_eq!(get_calendar_cell_at(280, 260, 50, 10, 10), None);
+        assert_eq!(get_calendar_cell_at(280, 260, 50, 0, 50), Some((0, 0)));
+        assert_eq!(get_calendar_cell_at(280, 260, 50, 85, 120), Some((2, 2)));
+        assert_eq!(get_calendar_cell_at(280, 260, 50, 279, 259), Some((5, 6))); // 除不尽的剩余部分算到最后一行和最后一列
+        assert_eq!(get_calendar_cell_at(0, 0, 50, 0, 60), None);
+    }
+
+    #[test]
+    fn calendar_cell_date() {
+        // 2026-10-01 是周四
+        assert_eq!(get_calendar_cell_date(2026, 10, 0, 0, 0), Some(NaiveDate::from_ymd(2026, 9, 27)));
+        assert_eq!(get_calendar_cell_date(2026, 10, 1, 0, 0), Some(NaiveDate::from_ymd(2026, 9, 28)));
+        assert_eq!(get_calendar_cell_date(2026, 10, 1, 0, 3), Some(NaiveDate::from_ymd(2026, 10, 1)));
+        assert_eq!(get_calendar_cell_date(2026, 10, 1, 5, 6), Some(NaiveDate::from_ymd(2026, 11, 8)));
+
+        // 2026-02-01 是周日, 第一行全是上个月的
+        assert_eq!(get_calendar_cell_date(2026, 2, 0, 0, 6), Some(NaiveDate::from_ymd(2026, 1, 31)));
+        assert_eq!(get_calendar_cell_date(2026, 2, 0, 1, 0), Some(NaiveDate::from_ymd(2026, 2, 1)));
+        assert_eq!(get_calendar_cell_date(2026, 2, 1, 0, 6), Some(NaiveDate::from_ymd(2026, 2, 1)));
+
+        assert_eq!(get_calendar_cell_date(2026, 13, 1, 0, 0), None);
+    }
+}